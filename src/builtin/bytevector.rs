@@ -0,0 +1,151 @@
+use super::*;
+use std::cell::RefCell;
+
+// Bytevectors
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("bytevector?", if_bytevector),
+        ("make-bytevector", make_bytevector),
+        ("bytevector", bytevector),
+        ("bytevector-length", bytevector_length),
+        ("bytevector-u8-ref", bytevector_u8_ref),
+        ("bytevector-u8-set!", bytevector_u8_set),
+        ("bytevector-copy", bytevector_copy),
+        ("bytevector-copy!", bytevector_copy_to),
+        ("bytevector-append", bytevector_append),
+        ("utf8->string", utf8_to_string),
+        ("string->utf8", string_to_utf8),
+    ]
+}
+
+fn new_bytevector(bytes: Vec<u8>) -> Option<Rc<Value>> {
+    let bytevector = Rc::new(Value::BYTEVECTOR(RefCell::new(bytes)));
+    limits::charge_value(&bytevector);
+    Some(bytevector)
+}
+
+fn to_bytevector_cell<'a>(value: &'a Value, name: &str) -> &'a RefCell<Vec<u8>> {
+    match value {
+        Value::BYTEVECTOR(bytes) => bytes,
+        _ => panic!("{}: not a bytevector", name),
+    }
+}
+
+fn to_bytevector(value: &Value, name: &str) -> Vec<u8> {
+    to_bytevector_cell(value, name).borrow().clone()
+}
+
+fn to_byte(value: &Value, name: &str) -> u8 {
+    let byte = to_integer(value, name);
+    u8::try_from(byte).unwrap_or_else(|_| panic!("{}: {} is not a byte", name, byte))
+}
+
+pub fn if_bytevector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::BYTEVECTOR(_)))
+}
+
+pub fn make_bytevector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let k = to_index(&args[0], "make-bytevector");
+    limits::check(k);
+    let fill = args.get(1).map_or(0, |b| to_byte(b, "make-bytevector"));
+    new_bytevector(vec![fill; k])
+}
+
+pub fn bytevector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    new_bytevector(args.iter().map(|b| to_byte(b, "bytevector")).collect())
+}
+
+pub fn bytevector_length(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let length = to_bytevector_cell(&args[0], "bytevector-length").borrow().len();
+    primitive(Primitive::NUMBER(length as i64))
+}
+
+pub fn bytevector_u8_ref(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let bytes = to_bytevector_cell(&args[0], "bytevector-u8-ref").borrow();
+    let k = to_index(&args[1], "bytevector-u8-ref");
+    match bytes.get(k) {
+        Some(byte) => primitive(Primitive::NUMBER(*byte as i64)),
+        None => panic!("bytevector-u8-ref: index {} out of range", k),
+    }
+}
+
+pub fn bytevector_u8_set(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 3 {
+        return None;
+    }
+    let mut bytes = to_bytevector_cell(&args[0], "bytevector-u8-set!").borrow_mut();
+    let k = to_index(&args[1], "bytevector-u8-set!");
+    let byte = to_byte(&args[2], "bytevector-u8-set!");
+    match bytes.get_mut(k) {
+        Some(b) => *b = byte,
+        None => panic!("bytevector-u8-set!: index {} out of range", k),
+    }
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn bytevector_copy(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let bytes = to_bytevector(&args[0], "bytevector-copy");
+    let (start, end) = range_args(&args, 1, bytes.len(), "bytevector-copy");
+    new_bytevector(bytes[start..end].to_vec())
+}
+
+pub fn bytevector_copy_to(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 3 || args.len() > 5 {
+        return None;
+    }
+    // copy first, `to` and `from` may be the same bytevector
+    let from = to_bytevector(&args[2], "bytevector-copy!");
+    let (start, end) = range_args(&args, 3, from.len(), "bytevector-copy!");
+    let mut to = to_bytevector_cell(&args[0], "bytevector-copy!").borrow_mut();
+    let at = to_index(&args[1], "bytevector-copy!");
+    if at + (end - start) > to.len() {
+        panic!("bytevector-copy!: not enough room in destination");
+    }
+    to[at..at + (end - start)].copy_from_slice(&from[start..end]);
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn bytevector_append(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    new_bytevector(
+        args.iter()
+            .flat_map(|b| to_bytevector(b, "bytevector-append"))
+            .collect(),
+    )
+}
+
+pub fn utf8_to_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let bytes = to_bytevector(&args[0], "utf8->string");
+    let (start, end) = range_args(&args, 1, bytes.len(), "utf8->string");
+    match String::from_utf8(bytes[start..end].to_vec()) {
+        Ok(string) => primitive(Primitive::STRING(RefCell::new(string))),
+        Err(_) => panic!("utf8->string: invalid utf-8 sequence"),
+    }
+}
+
+pub fn string_to_utf8(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let chars: Vec<char> = to_string(&args[0], "string->utf8").chars().collect();
+    let (start, end) = range_args(&args, 1, chars.len(), "string->utf8");
+    new_bytevector(chars[start..end].iter().collect::<String>().into_bytes())
+}
@@ -0,0 +1,208 @@
+use super::*;
+
+// Characters (scheme char)
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("char?", if_char),
+        ("char->integer", char_to_integer),
+        ("integer->char", integer_to_char),
+        ("char=?", char_eq),
+        ("char<?", char_lt),
+        ("char>?", char_gt),
+        ("char<=?", char_le),
+        ("char>=?", char_ge),
+        ("char-ci=?", char_ci_eq),
+        ("char-ci<?", char_ci_lt),
+        ("char-ci>?", char_ci_gt),
+        ("char-ci<=?", char_ci_le),
+        ("char-ci>=?", char_ci_ge),
+        ("char-alphabetic?", char_alphabetic),
+        ("char-numeric?", char_numeric),
+        ("char-whitespace?", char_whitespace),
+        ("char-upper-case?", char_upper_case),
+        ("char-lower-case?", char_lower_case),
+        ("char-upcase", char_upcase),
+        ("char-downcase", char_downcase),
+        ("char-foldcase", char_foldcase),
+        ("digit-value", digit_value),
+    ]
+}
+
+// The zero of every run of Unicode decimal digits (general category Nd).
+// Each run is ten consecutive code points, so a digit's value is its offset.
+const DECIMAL_ZEROS: [u32; 68] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66,
+    0x0CE6, 0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946,
+    0x19D0, 0x1A80, 0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0,
+    0xA9F0, 0xAA50, 0xABF0, 0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0,
+    0x112F0, 0x11450, 0x114D0, 0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50,
+    0x11DA0, 0x11F50, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6,
+    0x1E140, 0x1E2F0, 0x1E4F0, 0x1E950, 0x1FBF0,
+];
+
+fn decimal_value(c: char) -> Option<u32> {
+    let code = c as u32;
+    DECIMAL_ZEROS
+        .iter()
+        .find(|zero| (**zero..**zero + 10).contains(&code))
+        .map(|zero| code - zero)
+}
+
+// Case mappings that would expand to several characters leave the char unchanged.
+fn single(mut chars: impl Iterator<Item = char>, c: char) -> char {
+    match (chars.next(), chars.next()) {
+        (Some(mapped), None) => mapped,
+        _ => c,
+    }
+}
+
+fn upcase(c: char) -> char {
+    single(c.to_uppercase(), c)
+}
+
+fn downcase(c: char) -> char {
+    single(c.to_lowercase(), c)
+}
+
+fn foldcase(c: char) -> char {
+    downcase(c)
+}
+
+pub fn if_char(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    match &*args[0] {
+        Value::PRIMITIVE(primitive) => boolean(matches!(**primitive, Primitive::CHAR(_))),
+        _ => boolean(false),
+    }
+}
+
+pub fn char_to_integer(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    primitive(Primitive::NUMBER(to_char(&args[0], "char->integer") as i64))
+}
+
+pub fn integer_to_char(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let code = to_integer(&args[0], "integer->char");
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(c) => primitive(Primitive::CHAR(c)),
+        None => panic!("integer->char: {} is not a unicode scalar value", code),
+    }
+}
+
+macro_rules! compare_chars {
+    ($args:expr, $name:expr, $key:expr, $op:tt) => {{
+        if $args.is_empty() {
+            return None;
+        }
+        let chars: Vec<char> = $args.iter().map(|c| $key(to_char(c, $name))).collect();
+        boolean(chars.windows(2).all(|w| w[0] $op w[1]))
+    }};
+}
+
+pub fn char_eq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char=?", |c| c, ==)
+}
+
+pub fn char_lt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char<?", |c| c, <)
+}
+
+pub fn char_gt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char>?", |c| c, >)
+}
+
+pub fn char_le(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char<=?", |c| c, <=)
+}
+
+pub fn char_ge(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char>=?", |c| c, >=)
+}
+
+pub fn char_ci_eq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char-ci=?", foldcase, ==)
+}
+
+pub fn char_ci_lt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char-ci<?", foldcase, <)
+}
+
+pub fn char_ci_gt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char-ci>?", foldcase, >)
+}
+
+pub fn char_ci_le(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char-ci<=?", foldcase, <=)
+}
+
+pub fn char_ci_ge(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_chars!(args, "char-ci>=?", foldcase, >=)
+}
+
+macro_rules! char_predicate {
+    ($args:expr, $name:expr, $pred:expr) => {{
+        if $args.len() != 1 {
+            return None;
+        }
+        boolean($pred(to_char(&$args[0], $name)))
+    }};
+}
+
+pub fn char_alphabetic(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_predicate!(args, "char-alphabetic?", char::is_alphabetic)
+}
+
+pub fn char_numeric(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_predicate!(args, "char-numeric?", |c| decimal_value(c).is_some())
+}
+
+pub fn char_whitespace(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_predicate!(args, "char-whitespace?", char::is_whitespace)
+}
+
+pub fn char_upper_case(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_predicate!(args, "char-upper-case?", char::is_uppercase)
+}
+
+pub fn char_lower_case(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_predicate!(args, "char-lower-case?", char::is_lowercase)
+}
+
+macro_rules! char_mapping {
+    ($args:expr, $name:expr, $map:expr) => {{
+        if $args.len() != 1 {
+            return None;
+        }
+        primitive(Primitive::CHAR($map(to_char(&$args[0], $name))))
+    }};
+}
+
+pub fn char_upcase(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_mapping!(args, "char-upcase", upcase)
+}
+
+pub fn char_downcase(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_mapping!(args, "char-downcase", downcase)
+}
+
+pub fn char_foldcase(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    char_mapping!(args, "char-foldcase", foldcase)
+}
+
+pub fn digit_value(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    match decimal_value(to_char(&args[0], "digit-value")) {
+        Some(value) => primitive(Primitive::NUMBER(value as i64)),
+        None => boolean(false),
+    }
+}
@@ -0,0 +1,151 @@
+use super::*;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+// Evaluation
+//
+// `eval` writes the datum out and parses it again as a program, so it accepts
+// exactly what the parser does; objects that don't read back, like procedures,
+// are refused
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("eval", eval),
+        ("environment", environment),
+        ("scheme-report-environment", scheme_report_environment),
+        ("null-environment", null_environment),
+        ("environment?", if_environment),
+        ("load", load),
+    ]
+}
+
+fn to_env(value: &Value, name: &str) -> Rc<RefCell<Env>> {
+    match value {
+        Value::ENVIRONMENT(env) => env.clone(),
+        _ => panic!("{}: not an environment", name),
+    }
+}
+
+// Definitions evaluate to an unspecified value, an expression to its value
+pub fn eval(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let env = match args.get(1) {
+        Some(env) => to_env(env, "eval"),
+        None => Env::get_initialized_env(),
+    };
+    check_datum(&args[0], &mut HashSet::new());
+    let program = port::external(&args[0], true, false);
+    let mut result = Rc::new(Value::UNSPECIFIED);
+    for top in parse(&program).tops {
+        let definition = matches!(top, Top::DEC { .. });
+        let value = crate::interpreter::eval(top, &env).ok()?;
+        if !definition {
+            result = value?;
+        }
+    }
+    Some(result)
+}
+
+// The pairs and vectors of the datum being checked are `open`, reaching one
+// of them again is a cycle
+fn check_datum(value: &Value, open: &mut HashSet<*const Value>) {
+    let mut opened = Vec::new();
+    let mut value = value;
+    loop {
+        match value {
+            Value::PRIMITIVE(_) | Value::BYTEVECTOR(_) => break,
+            Value::PAIR { .. } | Value::VECTOR(_) => {
+                if !open.insert(value as *const Value) {
+                    panic!("eval: circular datum");
+                }
+                opened.push(value as *const Value);
+                match value {
+                    Value::PAIR { car, cdr } => {
+                        check_datum(car, open);
+                        value = cdr;
+                    }
+                    Value::VECTOR(values) => {
+                        for value in values.borrow().iter() {
+                            check_datum(value, open);
+                        }
+                        break;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => panic!("eval: not a datum"),
+        }
+    }
+    for object in opened {
+        open.remove(&object);
+    }
+}
+
+// (environment import-set ...), the import sets are read as those of an
+// `import` and bound over the builtins
+pub fn environment(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    let sets: Vec<String> = args.iter().map(|set| port::external(set, true, false)).collect();
+    let env = Env::get_initialized_env();
+    for top in parse(&format!("(import {})", sets.join(" "))).tops {
+        crate::interpreter::eval(top, &env).ok()?;
+    }
+    Some(Rc::new(Value::ENVIRONMENT(env)))
+}
+
+fn check_version(value: &Value, name: &str) {
+    if !matches!(to_integer(value, name), 5 | 7) {
+        panic!("{}: unsupported version", name);
+    }
+}
+
+// (scheme-report-environment 5), the 7 of R7RS is taken as well
+pub fn scheme_report_environment(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    check_version(&args[0], "scheme-report-environment");
+    Some(Rc::new(Value::ENVIRONMENT(Env::get_initialized_env())))
+}
+
+// The syntax alone, which the parser knows: only the internal builtins the
+// derived forms expand to are bound
+pub fn null_environment(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    check_version(&args[0], "null-environment");
+    let mut env = Env::new();
+    for (name, func) in super::get_builtins() {
+        if is_internal(name) {
+            env.insert(name.to_string(), Rc::new(Value::BUILTIN(func)));
+        }
+    }
+    Some(Rc::new(Value::ENVIRONMENT(Rc::new(RefCell::new(env)))))
+}
+
+pub fn if_environment(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::ENVIRONMENT(_)))
+}
+
+// (load filename [env]), into the environment of the top-level form by default
+pub fn load(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let path = to_string(&args[0], "load");
+    let env = match args.get(1) {
+        Some(env) => to_env(env, "load"),
+        None => top_level()?,
+    };
+    read_source(&path, "load", |program| {
+        for top in parse(&program).tops {
+            crate::interpreter::eval(top, &env).ok()?;
+        }
+        Some(Rc::new(Value::UNSPECIFIED))
+    })
+}
@@ -0,0 +1,385 @@
+use super::string::string_eq;
+use super::*;
+use crate::interpreter::apply;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// Hash tables (SRFI 69 and the common part of SRFI 125)
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("make-hash-table", make_hash_table),
+        ("hash-table?", if_hash_table),
+        ("hash-table-ref", hash_table_ref),
+        ("hash-table-ref/default", hash_table_ref_default),
+        ("hash-table-set!", hash_table_set),
+        ("hash-table-delete!", hash_table_delete),
+        ("hash-table-contains?", hash_table_contains),
+        ("hash-table-exists?", hash_table_contains),
+        ("hash-table-update!", hash_table_update),
+        ("hash-table-update!/default", hash_table_update_default),
+        ("hash-table-size", hash_table_size),
+        ("hash-table-count", hash_table_count),
+        ("hash-table-keys", hash_table_keys),
+        ("hash-table-values", hash_table_values),
+        ("hash-table->alist", hash_table_to_alist),
+        ("hash-table-walk", hash_table_walk),
+        ("hash-table-clear!", hash_table_clear),
+        ("hash-table-copy", hash_table_copy),
+        ("hash", hash),
+        ("string-hash", string_hash),
+        ("hash-by-identity", hash_by_identity),
+    ]
+}
+
+// Hashing
+//
+// Structural hashing stops after a fixed number of nodes, which bounds the
+// cost on long lists and makes it terminate on cyclic data. Equal values are
+// walked in the same order, so they still hash alike.
+
+const HASH_BUDGET: usize = 64;
+
+fn hash_eq(value: &Rc<Value>, hasher: &mut DefaultHasher) {
+    std::mem::discriminant(&**value).hash(hasher);
+    match &**value {
+        Value::PRIMITIVE(primitive) => {
+            std::mem::discriminant(&**primitive).hash(hasher);
+            match &**primitive {
+                Primitive::NUMBER(n) => n.hash(hasher),
+                Primitive::BOOLEAN(b) => b.hash(hasher),
+                Primitive::CHAR(c) => c.hash(hasher),
+                Primitive::SYMBOL(s) => s.hash(hasher),
+                Primitive::STRING(_) => Rc::as_ptr(primitive).hash(hasher),
+                Primitive::NIL => (),
+            }
+        }
+        Value::CLOSURE(clojure) => Rc::as_ptr(clojure).hash(hasher),
+        Value::BUILTIN(builtin) => (*builtin as usize).hash(hasher),
+        Value::RECORDTYPE(rtd) => Rc::as_ptr(rtd).hash(hasher),
+        Value::UNSPECIFIED | Value::EOF => (),
+        _ => Rc::as_ptr(value).hash(hasher),
+    }
+}
+
+fn hash_equal(value: &Rc<Value>, hasher: &mut DefaultHasher, budget: &mut usize) {
+    if *budget == 0 {
+        return;
+    }
+    *budget -= 1;
+    match &**value {
+        Value::PRIMITIVE(primitive) => match &**primitive {
+            Primitive::STRING(string) => string.borrow().hash(hasher),
+            _ => hash_eq(value, hasher),
+        },
+        Value::PAIR { car, cdr } => {
+            "pair".hash(hasher);
+            hash_equal(car, hasher, budget);
+            hash_equal(cdr, hasher, budget);
+        }
+        Value::VECTOR(values) => {
+            values.borrow().len().hash(hasher);
+            for value in values.borrow().iter() {
+                hash_equal(value, hasher, budget);
+            }
+        }
+        Value::BYTEVECTOR(bytes) => bytes.borrow().hash(hasher),
+        Value::RECORD { rtd, fields } => {
+            Rc::as_ptr(rtd).hash(hasher);
+            for field in fields.borrow().iter() {
+                hash_equal(field, hasher, budget);
+            }
+        }
+        _ => hash_eq(value, hasher),
+    }
+}
+
+fn hash_key(equivalence: Equivalence, key: &Rc<Value>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match equivalence {
+        // `eqv?` coincides with `eq?`
+        Equivalence::EQ | Equivalence::EQV => hash_eq(key, &mut hasher),
+        Equivalence::EQUAL => hash_equal(key, &mut hasher, &mut HASH_BUDGET.clone()),
+        Equivalence::STRING => to_string(key, "hash-table").hash(&mut hasher),
+    }
+    hasher.finish()
+}
+
+fn same_key(equivalence: Equivalence, k1: &Rc<Value>, k2: &Rc<Value>) -> bool {
+    match equivalence {
+        Equivalence::EQ => is_eq(k1, k2),
+        Equivalence::EQV => is_eqv(k1, k2),
+        Equivalence::EQUAL => is_equal(k1, k2),
+        Equivalence::STRING => to_string(k1, "hash-table") == to_string(k2, "hash-table"),
+    }
+}
+
+fn to_table<'a>(value: &'a Value, name: &str) -> &'a RefCell<HashTable> {
+    match value {
+        Value::HASHTABLE(table) => table,
+        _ => panic!("{}: not a hash table", name),
+    }
+}
+
+impl HashTable {
+    fn get(&self, key: &Rc<Value>) -> Option<Rc<Value>> {
+        let bucket = self.buckets.get(&hash_key(self.equivalence, key))?;
+        bucket
+            .iter()
+            .find(|(k, _)| same_key(self.equivalence, k, key))
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn set(&mut self, key: Rc<Value>, value: Rc<Value>) {
+        let equivalence = self.equivalence;
+        let bucket = self.buckets.entry(hash_key(equivalence, &key)).or_default();
+        match bucket.iter_mut().find(|(k, _)| same_key(equivalence, k, &key)) {
+            Some(entry) => entry.1 = value,
+            None => bucket.push((key, value)),
+        }
+    }
+
+    fn delete(&mut self, key: &Rc<Value>) -> bool {
+        let equivalence = self.equivalence;
+        let hash = hash_key(equivalence, key);
+        let Some(bucket) = self.buckets.get_mut(&hash) else {
+            return false;
+        };
+        let size = bucket.len();
+        bucket.retain(|(k, _)| !same_key(equivalence, k, key));
+        let deleted = bucket.len() != size;
+        if bucket.is_empty() {
+            self.buckets.remove(&hash);
+        }
+        deleted
+    }
+
+    pub fn entries(&self) -> Vec<(Rc<Value>, Rc<Value>)> {
+        self.buckets.values().flatten().cloned().collect()
+    }
+}
+
+fn new_table(equivalence: Equivalence, buckets: Buckets) -> Option<Rc<Value>> {
+    Some(Rc::new(Value::HASHTABLE(RefCell::new(HashTable {
+        equivalence,
+        buckets,
+    }))))
+}
+
+// (make-hash-table [equivalence [hash]]), keys are compared with `equal?` by
+// default; a hash function is accepted but the table hashes consistently with
+// the equivalence on its own
+pub fn make_hash_table(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 2 {
+        return None;
+    }
+    let equivalence = match args.first().map(|arg| &**arg) {
+        None => Equivalence::EQUAL,
+        Some(Value::BUILTIN(f)) if std::ptr::fn_addr_eq(*f, eq as Builtin) => Equivalence::EQ,
+        Some(Value::BUILTIN(f)) if std::ptr::fn_addr_eq(*f, eqv as Builtin) => Equivalence::EQV,
+        Some(Value::BUILTIN(f)) if std::ptr::fn_addr_eq(*f, equal as Builtin) => Equivalence::EQUAL,
+        Some(Value::BUILTIN(f)) if std::ptr::fn_addr_eq(*f, string_eq as Builtin) => Equivalence::STRING,
+        _ => panic!("make-hash-table: unsupported equivalence"),
+    };
+    new_table(equivalence, HashMap::new())
+}
+
+pub fn if_hash_table(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::HASHTABLE(_)))
+}
+
+// (hash-table-ref table key [failure [success]])
+pub fn hash_table_ref(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 || args.len() > 4 {
+        return None;
+    }
+    let found = to_table(&args[0], "hash-table-ref").borrow().get(&args[1]);
+    match (found, args.get(2), args.get(3)) {
+        (Some(value), _, Some(success)) => apply(success.clone(), vec![value]),
+        (Some(value), _, None) => Some(value),
+        (None, Some(failure), _) => apply(failure.clone(), Vec::new()),
+        (None, None, _) => panic!("hash-table-ref: no value for key {}", args[1]),
+    }
+}
+
+pub fn hash_table_ref_default(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 3 {
+        return None;
+    }
+    let found = to_table(&args[0], "hash-table-ref/default").borrow().get(&args[1]);
+    Some(found.unwrap_or_else(|| args[2].clone()))
+}
+
+// (hash-table-set! table key value ...)
+pub fn hash_table_set(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 3 || args.len().is_multiple_of(2) {
+        return None;
+    }
+    let mut table = to_table(&args[0], "hash-table-set!").borrow_mut();
+    for entry in args[1..].chunks(2) {
+        table.set(entry[0].clone(), entry[1].clone());
+    }
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+// (hash-table-delete! table key ...) returns the number of keys deleted
+pub fn hash_table_delete(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() {
+        return None;
+    }
+    let mut table = to_table(&args[0], "hash-table-delete!").borrow_mut();
+    let deleted = args[1..].iter().filter(|key| table.delete(key)).count();
+    primitive(Primitive::NUMBER(deleted as i64))
+}
+
+pub fn hash_table_contains(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    boolean(to_table(&args[0], "hash-table-contains?").borrow().get(&args[1]).is_some())
+}
+
+// (hash-table-update! table key updater [failure [success]])
+pub fn hash_table_update(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 3 || args.len() > 5 {
+        return None;
+    }
+    let mut ref_args = vec![args[0].clone(), args[1].clone()];
+    ref_args.extend(args[3..].iter().cloned());
+    let current = hash_table_ref(ref_args)?;
+    let value = apply(args[2].clone(), vec![current])?;
+    to_table(&args[0], "hash-table-update!").borrow_mut().set(args[1].clone(), value);
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+// (hash-table-update!/default table key updater default)
+pub fn hash_table_update_default(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 4 {
+        return None;
+    }
+    let current = hash_table_ref_default(vec![args[0].clone(), args[1].clone(), args[3].clone()])?;
+    let value = apply(args[2].clone(), vec![current])?;
+    to_table(&args[0], "hash-table-update!/default").borrow_mut().set(args[1].clone(), value);
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn hash_table_size(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let table = to_table(&args[0], "hash-table-size").borrow();
+    let size: usize = table.buckets.values().map(Vec::len).sum();
+    primitive(Primitive::NUMBER(size as i64))
+}
+
+// (hash-table-count table) is the size as in SRFI 69, (hash-table-count pred table)
+// the number of entries satisfying pred as in SRFI 125
+pub fn hash_table_count(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    match args.len() {
+        1 => hash_table_size(args),
+        2 => {
+            let entries = to_table(&args[1], "hash-table-count").borrow().entries();
+            let mut count = 0;
+            for (key, value) in entries {
+                if apply(args[0].clone(), vec![key, value])?.is_true() {
+                    count += 1;
+                }
+            }
+            primitive(Primitive::NUMBER(count))
+        }
+        _ => None,
+    }
+}
+
+pub fn hash_table_keys(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let entries = to_table(&args[0], "hash-table-keys").borrow().entries();
+    Some(vec_to_list(entries.into_iter().map(|(key, _)| key).collect()))
+}
+
+pub fn hash_table_values(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let entries = to_table(&args[0], "hash-table-values").borrow().entries();
+    Some(vec_to_list(entries.into_iter().map(|(_, value)| value).collect()))
+}
+
+pub fn hash_table_to_alist(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let entries = to_table(&args[0], "hash-table->alist").borrow().entries();
+    Some(vec_to_list(
+        entries
+            .into_iter()
+            .map(|(car, cdr)| Rc::new(Value::PAIR { car, cdr }))
+            .collect(),
+    ))
+}
+
+// (hash-table-walk table proc) calls proc with each key and value; the entries
+// are collected first so proc may modify the table
+pub fn hash_table_walk(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let entries = to_table(&args[0], "hash-table-walk").borrow().entries();
+    for (key, value) in entries {
+        apply(args[1].clone(), vec![key, value])?;
+    }
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn hash_table_clear(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    to_table(&args[0], "hash-table-clear!").borrow_mut().buckets.clear();
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn hash_table_copy(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let table = to_table(&args[0], "hash-table-copy").borrow();
+    new_table(table.equivalence, table.buckets.clone())
+}
+
+// Hash values are made non-negative fixnums, (hash obj [bound])
+fn hash_value(hash: u64, bound: Option<&Rc<Value>>, name: &str) -> Option<Rc<Value>> {
+    let hash = hash >> 1;
+    let hash = match bound {
+        Some(bound) => hash % (to_index(bound, name).max(1) as u64),
+        None => hash,
+    };
+    primitive(Primitive::NUMBER(hash as i64))
+}
+
+pub fn hash(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    hash_value(hash_key(Equivalence::EQUAL, &args[0]), args.get(1), "hash")
+}
+
+pub fn string_hash(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    hash_value(hash_key(Equivalence::STRING, &args[0]), args.get(1), "string-hash")
+}
+
+pub fn hash_by_identity(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    hash_value(hash_key(Equivalence::EQ, &args[0]), args.get(1), "hash-by-identity")
+}
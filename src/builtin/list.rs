@@ -0,0 +1,400 @@
+use super::*;
+use crate::interpreter::apply;
+
+// Pairs and lists
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("pair?", if_pair),
+        ("cons", cons),
+        ("car", car),
+        ("cdr", cdr),
+        ("caar", caar),
+        ("cadr", cadr),
+        ("cdar", cdar),
+        ("cddr", cddr),
+        ("caaar", caaar),
+        ("caadr", caadr),
+        ("cadar", cadar),
+        ("caddr", caddr),
+        ("cdaar", cdaar),
+        ("cdadr", cdadr),
+        ("cddar", cddar),
+        ("cdddr", cdddr),
+        ("caaaar", caaaar),
+        ("caaadr", caaadr),
+        ("caadar", caadar),
+        ("caaddr", caaddr),
+        ("cadaar", cadaar),
+        ("cadadr", cadadr),
+        ("caddar", caddar),
+        ("cadddr", cadddr),
+        ("cdaaar", cdaaar),
+        ("cdaadr", cdaadr),
+        ("cdadar", cdadar),
+        ("cdaddr", cdaddr),
+        ("cddaar", cddaar),
+        ("cddadr", cddadr),
+        ("cdddar", cdddar),
+        ("cddddr", cddddr),
+        ("null?", if_null),
+        ("list?", if_list),
+        ("make-list", make_list),
+        ("list", list),
+        ("length", length),
+        ("append", append),
+        ("reverse", reverse),
+        ("list-tail", list_tail),
+        ("list-ref", list_ref),
+        ("list-copy", list_copy),
+        ("memq", memq),
+        ("memv", memv),
+        ("member", member),
+        ("assq", assq),
+        ("assv", assv),
+        ("assoc", assoc),
+        ("map", map),
+        ("for-each", for_each),
+        ("apply", apply_procedure),
+        // what quasiquote and stream-cons expand to
+        (" cons", cons),
+        (" append", append),
+    ]
+}
+
+fn first(value: &Rc<Value>, name: &str) -> Rc<Value> {
+    match &**value {
+        Value::PAIR { car, cdr: _ } => car.clone(),
+        _ => panic!("{}: not a pair", name),
+    }
+}
+
+fn rest(value: &Rc<Value>, name: &str) -> Rc<Value> {
+    match &**value {
+        Value::PAIR { car: _, cdr } => cdr.clone(),
+        _ => panic!("{}: not a pair", name),
+    }
+}
+
+pub fn if_pair(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::PAIR { .. }))
+}
+
+pub fn cons(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    limits::charge(limits::size_of_values(1));
+    Some(Rc::new(Value::PAIR {
+        car: args[0].clone(),
+        cdr: args[1].clone(),
+    }))
+}
+
+pub fn car(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(first(&args[0], "car"))
+}
+
+pub fn cdr(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(rest(&args[0], "cdr"))
+}
+
+pub fn caar(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(first(&first(&args[0], "caar"), "caar"))
+}
+
+pub fn cadr(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(first(&rest(&args[0], "cadr"), "cadr"))
+}
+
+pub fn cdar(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(rest(&first(&args[0], "cdar"), "cdar"))
+}
+
+pub fn cddr(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(rest(&rest(&args[0], "cddr"), "cddr"))
+}
+
+// The deeper compositions of (scheme cxr), the letters between c and r name
+// the accessors applied from right to left
+fn cxr(value: &Rc<Value>, name: &str) -> Rc<Value> {
+    name[1..name.len() - 1]
+        .chars()
+        .rev()
+        .fold(value.clone(), |value, c| match c {
+            'a' => first(&value, name),
+            _ => rest(&value, name),
+        })
+}
+
+macro_rules! cxr {
+    ($($name:ident),*) => {
+        $(
+            pub fn $name(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+                if args.len() != 1 {
+                    return None;
+                }
+                Some(cxr(&args[0], stringify!($name)))
+            }
+        )*
+    };
+}
+
+cxr!(
+    caaar, caadr, cadar, caddr, cdaar, cdadr, cddar, cdddr,
+    caaaar, caaadr, caadar, caaddr, cadaar, cadadr, caddar, cadddr,
+    cdaaar, cdaadr, cdadar, cdaddr, cddaar, cddadr, cdddar, cddddr
+);
+
+pub fn if_null(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(args[0].is_nil())
+}
+
+pub fn if_list(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    // a circular list isn't one, `slow` only meets the list again on a cycle
+    let mut list = args[0].clone();
+    let mut slow = list.clone();
+    let mut steps = 0;
+    while let Value::PAIR { car: _, cdr } = &*list {
+        list = cdr.clone();
+        steps += 1;
+        if steps % 2 == 0 {
+            slow = rest(&slow, "list?");
+            if Rc::ptr_eq(&slow, &list) {
+                return boolean(false);
+            }
+        }
+    }
+    boolean(list.is_nil())
+}
+
+pub fn make_list(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let k = to_index(&args[0], "make-list");
+    limits::check(limits::size_of_values(k));
+    let fill = args.get(1).cloned().unwrap_or_else(|| Rc::new(Value::UNSPECIFIED));
+    Some(vec_to_list(vec![fill; k]))
+}
+
+pub fn list(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    Some(vec_to_list(args))
+}
+
+pub fn length(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let length = list_to_vec(&args[0], "length").len();
+    primitive(Primitive::NUMBER(length as i64))
+}
+
+pub fn append(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    let mut args = args;
+    let mut result = args.pop().unwrap_or_else(Value::nil);
+    let lists: Vec<_> = args.iter().map(|list| list_to_vec(list, "append")).collect();
+    limits::charge(limits::size_of_values(lists.iter().map(Vec::len).sum()));
+    for list in lists.into_iter().rev() {
+        for car in list.into_iter().rev() {
+            result = Rc::new(Value::PAIR { car, cdr: result });
+        }
+    }
+    Some(result)
+}
+
+pub fn reverse(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let mut values = list_to_vec(&args[0], "reverse");
+    values.reverse();
+    Some(vec_to_list(values))
+}
+
+pub fn list_tail(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let mut list = args[0].clone();
+    for _ in 0..to_index(&args[1], "list-tail") {
+        list = rest(&list, "list-tail");
+    }
+    Some(list)
+}
+
+pub fn list_ref(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let mut list = args[0].clone();
+    for _ in 0..to_index(&args[1], "list-ref") {
+        list = rest(&list, "list-ref");
+    }
+    Some(first(&list, "list-ref"))
+}
+
+pub fn list_copy(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    // an improper tail is shared, not copied
+    let mut values = Vec::new();
+    let mut list = args[0].clone();
+    while let Value::PAIR { car, cdr } = &*list {
+        values.push(car.clone());
+        list = cdr.clone();
+    }
+    limits::charge(limits::size_of_values(values.len()));
+    Some(
+        values
+            .into_iter()
+            .rev()
+            .fold(list, |cdr, car| Rc::new(Value::PAIR { car, cdr })),
+    )
+}
+
+// The first pair of `list` whose car matches `obj`
+fn find_member(
+    obj: &Rc<Value>,
+    list: &Rc<Value>,
+    matches: impl Fn(&Rc<Value>, &Rc<Value>) -> Option<bool>,
+) -> Option<Rc<Value>> {
+    let mut list = list.clone();
+    while let Value::PAIR { car, cdr } = &*list {
+        if matches(obj, car)? {
+            return Some(list);
+        }
+        list = cdr.clone();
+    }
+    boolean(false)
+}
+
+// The first pair in association list `alist` whose car matches `obj`
+fn find_assoc(
+    obj: &Rc<Value>,
+    alist: &Rc<Value>,
+    name: &str,
+    matches: impl Fn(&Rc<Value>, &Rc<Value>) -> Option<bool>,
+) -> Option<Rc<Value>> {
+    for entry in list_to_vec(alist, name) {
+        if matches(obj, &first(&entry, name))? {
+            return Some(entry);
+        }
+    }
+    boolean(false)
+}
+
+// Compare with the optional user supplied predicate, or `equal?` by default
+fn compare_with(compare: Option<&Rc<Value>>) -> impl Fn(&Rc<Value>, &Rc<Value>) -> Option<bool> + '_ {
+    move |a, b| match compare {
+        Some(compare) => {
+            let result = apply(compare.clone(), vec![a.clone(), b.clone()])?;
+            Some(result.is_true())
+        }
+        None => Some(is_equal(a, b)),
+    }
+}
+
+pub fn memq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    find_member(&args[0], &args[1], |a, b| Some(is_eq(a, b)))
+}
+
+pub fn memv(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    find_member(&args[0], &args[1], |a, b| Some(is_eqv(a, b)))
+}
+
+pub fn member(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 || args.len() > 3 {
+        return None;
+    }
+    find_member(&args[0], &args[1], compare_with(args.get(2)))
+}
+
+pub fn assq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    find_assoc(&args[0], &args[1], "assq", |a, b| Some(is_eq(a, b)))
+}
+
+pub fn assv(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    find_assoc(&args[0], &args[1], "assv", |a, b| Some(is_eqv(a, b)))
+}
+
+pub fn assoc(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 || args.len() > 3 {
+        return None;
+    }
+    find_assoc(&args[0], &args[1], "assoc", compare_with(args.get(2)))
+}
+
+// Call `proc` on the i-th elements of all `lists`, up to the shortest one
+fn map_lists(args: &[Rc<Value>], name: &str) -> Option<Vec<Rc<Value>>> {
+    let proc = args[0].clone();
+    let lists: Vec<Vec<Rc<Value>>> = args[1..].iter().map(|l| list_to_vec(l, name)).collect();
+    let len = lists.iter().map(Vec::len).min()?;
+    (0..len)
+        .map(|i| apply(proc.clone(), lists.iter().map(|l| l[i].clone()).collect()))
+        .collect()
+}
+
+pub fn map(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    Some(vec_to_list(map_lists(&args, "map")?))
+}
+
+pub fn for_each(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    map_lists(&args, "for-each")?;
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn apply_procedure(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    let mut operands = args[1..args.len() - 1].to_vec();
+    operands.extend(list_to_vec(&args[args.len() - 1], "apply"));
+    apply(args[0].clone(), operands)
+}
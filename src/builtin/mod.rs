@@ -0,0 +1,537 @@
+use crate::interpreter::*;
+use crate::limits;
+use crate::parser::*;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+mod bytevector;
+mod character;
+mod eval;
+mod hashtable;
+mod list;
+mod port;
+mod promise;
+mod record;
+mod stream;
+mod string;
+mod vector;
+
+// Get all the builtin functions
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    let mut builtins: Vec<(&'static str, Builtin)> = vec![
+        ("eq?", eq),
+        ("eqv?", eqv),
+        // what case expands to
+        (" eqv?", eqv),
+        ("equal?", equal),
+        ("number?", if_number),
+        ("=", equal_number),
+        ("+", plus_number),
+        ("-", minus_number),
+        ("*", times_number),
+        ("/", divide_number),
+        ("<", less_number),
+        (">", greater_number),
+        ("not", not),
+        ("boolean?", if_boolean),
+        ("symbol?", if_symbol),
+        ("procedure?", if_procedure),
+        ("procedure-arity", procedure_arity),
+        ("values", values),
+        // what define-record-type expands to
+        (" values", values),
+        ("call-with-values", call_with_values),
+        ("make-parameter", make_parameter),
+        (" parameterize", parameterize),
+    ];
+    builtins.extend(list::get_builtins());
+    builtins.extend(character::get_builtins());
+    builtins.extend(string::get_builtins());
+    builtins.extend(vector::get_builtins());
+    builtins.extend(bytevector::get_builtins());
+    builtins.extend(record::get_builtins());
+    builtins.extend(promise::get_builtins());
+    builtins.extend(stream::get_builtins());
+    builtins.extend(hashtable::get_builtins());
+    builtins.extend(port::get_builtins());
+    builtins.extend(eval::get_builtins());
+    builtins
+}
+
+// Derived forms expand to calls of builtins named with a leading space, which
+// no identifier in source code can spell, so no script shadows or calls them
+pub fn is_internal(name: &str) -> bool {
+    name.starts_with(' ')
+}
+
+pub use port::Ports;
+
+pub const PRELUDE: &str = include_str!("prelude.scm");
+
+// Get the variables bound to values other than builtin functions
+
+pub fn get_constants() -> Vec<(&'static str, Rc<Value>)> {
+    let mut constants = stream::get_constants();
+    constants.extend(port::get_constants());
+    constants
+}
+
+// Helpers shared by the builtin libraries
+
+fn primitive(primitive: Primitive) -> Option<Rc<Value>> {
+    let value = Rc::new(Value::PRIMITIVE(Rc::new(primitive)));
+    limits::charge_value(&value);
+    Some(value)
+}
+
+fn boolean(b: bool) -> Option<Rc<Value>> {
+    primitive(Primitive::BOOLEAN(b))
+}
+
+fn to_integer(value: &Value, name: &str) -> i64 {
+    match value {
+        Value::PRIMITIVE(primitive) => match **primitive {
+            Primitive::NUMBER(n) => n,
+            _ => panic!("{}: not a number", name),
+        },
+        _ => panic!("{}: not a number", name),
+    }
+}
+
+fn to_index(value: &Value, name: &str) -> usize {
+    usize::try_from(to_integer(value, name)).unwrap_or_else(|_| panic!("{}: negative index", name))
+}
+
+fn to_char(value: &Value, name: &str) -> char {
+    match value {
+        Value::PRIMITIVE(primitive) => match **primitive {
+            Primitive::CHAR(c) => c,
+            _ => panic!("{}: not a char", name),
+        },
+        _ => panic!("{}: not a char", name),
+    }
+}
+
+fn to_str_cell<'a>(value: &'a Value, name: &str) -> &'a RefCell<String> {
+    match value {
+        Value::PRIMITIVE(primitive) => match &**primitive {
+            Primitive::STRING(string) => string,
+            _ => panic!("{}: not a string", name),
+        },
+        _ => panic!("{}: not a string", name),
+    }
+}
+
+fn to_string(value: &Value, name: &str) -> String {
+    to_str_cell(value, name).borrow().clone()
+}
+
+// The optional [start [end]] arguments found at `from` in `args`
+fn range_args(args: &[Rc<Value>], from: usize, len: usize, name: &str) -> (usize, usize) {
+    let index = |i: usize, default: usize| args.get(i).map_or(default, |arg| to_index(arg, name));
+    let (start, end) = (index(from, 0), index(from + 1, len));
+    if start > end || end > len {
+        panic!("{}: range {}..{} out of bounds for length {}", name, start, end, len);
+    }
+    (start, end)
+}
+
+// A circular list is an error rather than a loop, `slow` goes along the list
+// at half the pace and is only caught up with on a cycle
+fn list_to_vec(list: &Rc<Value>, name: &str) -> Vec<Rc<Value>> {
+    let mut values = Vec::new();
+    let mut list = list.clone();
+    let mut slow = list.clone();
+    loop {
+        list = match &*list {
+            Value::PAIR { car, cdr } => {
+                values.push(car.clone());
+                cdr.clone()
+            }
+            _ if list.is_nil() => return values,
+            _ => panic!("{}: not a proper list", name),
+        };
+        if values.len() % 2 == 0 {
+            slow = rest_of(&slow);
+            if Rc::ptr_eq(&slow, &list) {
+                panic!("{}: circular list", name);
+            }
+        }
+    }
+}
+
+// The cdr of a pair along a list already walked
+fn rest_of(pair: &Rc<Value>) -> Rc<Value> {
+    match &**pair {
+        Value::PAIR { cdr, .. } => cdr.clone(),
+        _ => unreachable!(),
+    }
+}
+
+fn vec_to_list(values: Vec<Rc<Value>>) -> Rc<Value> {
+    limits::charge(limits::size_of_values(values.len()));
+    values
+        .into_iter()
+        .rev()
+        .fold(Value::nil(), |cdr, car| Rc::new(Value::PAIR { car, cdr }))
+}
+
+// Equivalence predicates
+
+fn is_eq(v1: &Rc<Value>, v2: &Rc<Value>) -> bool {
+    if Rc::ptr_eq(v1, v2) {
+        return true;
+    }
+    match (&**v1, &**v2) {
+        // strings are the only primitives with an identity of their own
+        (Value::PRIMITIVE(p1), Value::PRIMITIVE(p2)) => match (&**p1, &**p2) {
+            (Primitive::STRING(_), Primitive::STRING(_)) => Rc::ptr_eq(p1, p2),
+            _ => p1 == p2,
+        },
+        (Value::CLOSURE(c1), Value::CLOSURE(c2)) => Rc::ptr_eq(c1, c2),
+        (Value::BUILTIN(f1), Value::BUILTIN(f2)) => std::ptr::fn_addr_eq(*f1, *f2),
+        (Value::RECORDTYPE(t1), Value::RECORDTYPE(t2)) => Rc::ptr_eq(t1, t2),
+        (Value::UNSPECIFIED, Value::UNSPECIFIED) => true,
+        (Value::EOF, Value::EOF) => true,
+        _ => false,
+    }
+}
+
+// All numbers are fixnums and chars are immediate, so `eqv?` coincides with `eq?`
+fn is_eqv(v1: &Rc<Value>, v2: &Rc<Value>) -> bool {
+    is_eq(v1, v2)
+}
+
+fn is_equal(v1: &Rc<Value>, v2: &Rc<Value>) -> bool {
+    equal_with(v1, v2, &mut HashSet::new())
+}
+
+// Compare structurally; a pair of objects already under comparison is assumed
+// equal, which makes the walk terminate on cyclic data.
+fn equal_with(
+    v1: &Rc<Value>,
+    v2: &Rc<Value>,
+    visited: &mut HashSet<(*const Value, *const Value)>,
+) -> bool {
+    if is_eqv(v1, v2) {
+        return true;
+    }
+    match (&**v1, &**v2) {
+        (Value::PRIMITIVE(p1), Value::PRIMITIVE(p2)) => p1 == p2,
+        (Value::BYTEVECTOR(b1), Value::BYTEVECTOR(b2)) => *b1.borrow() == *b2.borrow(),
+        (Value::PAIR { .. }, Value::PAIR { .. }) | (Value::VECTOR(_), Value::VECTOR(_)) => {
+            if !visited.insert((Rc::as_ptr(v1), Rc::as_ptr(v2))) {
+                return true;
+            }
+            match (&**v1, &**v2) {
+                (Value::PAIR { car: a1, cdr: d1 }, Value::PAIR { car: a2, cdr: d2 }) => {
+                    equal_with(a1, a2, visited) && equal_with(d1, d2, visited)
+                }
+                (Value::VECTOR(e1), Value::VECTOR(e2)) => {
+                    let (e1, e2) = (e1.borrow().clone(), e2.borrow().clone());
+                    e1.len() == e2.len()
+                        && e1.iter().zip(&e2).all(|(e1, e2)| equal_with(e1, e2, visited))
+                }
+                _ => unreachable!(),
+            }
+        }
+        (Value::RECORD { rtd: t1, fields: f1 }, Value::RECORD { rtd: t2, fields: f2 }) => {
+            if !Rc::ptr_eq(t1, t2) {
+                return false;
+            }
+            if !visited.insert((Rc::as_ptr(v1), Rc::as_ptr(v2))) {
+                return true;
+            }
+            let (f1, f2) = (f1.borrow().clone(), f2.borrow().clone());
+            f1.iter().zip(&f2).all(|(f1, f2)| equal_with(f1, f2, visited))
+        }
+        _ => false,
+    }
+}
+
+pub fn eq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    boolean(is_eq(&args[0], &args[1]))
+}
+
+pub fn eqv(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    boolean(is_eqv(&args[0], &args[1]))
+}
+
+pub fn equal(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    boolean(is_equal(&args[0], &args[1]))
+}
+
+// Numbers
+
+pub fn if_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    match &*args[0] {
+        Value::PRIMITIVE(datum) => match datum.as_ref() {
+            Primitive::NUMBER(_) => {
+                Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true)))))
+            }
+            _ => Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(
+                false,
+            ))))),
+        },
+        _ => None,
+    }
+}
+
+macro_rules! binop_number {
+    ($args:expr, $op:expr) => {
+        if $args.len() != 2 {
+            return None;
+        } else {
+            match $args.as_slice() {
+                [n1, n2] => match (&**n1, &**n2) {
+                    (Value::PRIMITIVE(d1), Value::PRIMITIVE(d2)) => match (&**d1, &**d2) {
+                        (Primitive::NUMBER(n1), Primitive::NUMBER(n2)) => {
+                            Some(Rc::new(Value::PRIMITIVE(Rc::new($op(n1, n2)))))
+                        }
+                        _ => panic!("binop_number! called with non-number arguments"),
+                    },
+                    _ => panic!("binop_number! called with non-primitive arguments"),
+                },
+                _ => panic!("binop_number! called with non-array arguments"),
+            }
+        }
+    };
+}
+
+pub fn equal_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::BOOLEAN(n1 == n2))
+}
+
+pub fn less_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::BOOLEAN(n1 < n2))
+}
+
+pub fn greater_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::BOOLEAN(n1 > n2))
+}
+
+pub fn plus_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::NUMBER(n1 + n2))
+}
+
+pub fn minus_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::NUMBER(n1 - n2))
+}
+
+pub fn times_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::NUMBER(n1 * n2))
+}
+
+pub fn divide_number(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    binop_number!(args, |n1, n2| Primitive::NUMBER(n1 / n2))
+}
+
+// Booleans
+
+pub fn not(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        None
+    } else {
+        boolean(!args[0].is_true())
+    }
+}
+
+pub fn if_boolean(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        None
+    } else {
+        match &*args[0] {
+            Value::PRIMITIVE(datum) => match datum.as_ref() {
+                Primitive::BOOLEAN(_) => {
+                    Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true)))))
+                }
+                _ => Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(
+                    false,
+                ))))),
+            },
+            _ => Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true))))),
+        }
+    }
+}
+
+// Symbols
+
+pub fn if_symbol(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        None
+    } else {
+        match &*args[0] {
+            Value::PRIMITIVE(primitive) => match primitive.as_ref() {
+                Primitive::SYMBOL(_) => {
+                    Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true)))))
+                }
+                _ => Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(
+                    false,
+                ))))),
+            },
+            _ => Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(
+                false,
+            ))))),
+        }
+    }
+}
+
+
+// Control features
+
+pub fn if_procedure(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        None
+    } else {
+        match &*args[0] {
+            Value::CLOSURE(_) | Value::CASELAMBDA(_) | Value::BUILTIN(_) | Value::PARAMETER(_) => {
+                Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true)))))
+            }
+            _ => Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::BOOLEAN(
+                false,
+            ))))),
+        }
+    }
+}
+
+// `(min . max)` with max #f when there is a rest parameter, a list of those for
+// a case-lambda, `(0 . 0)` for parameters, and #f for builtins whose arity is
+// checked by the builtin itself
+pub fn procedure_arity(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    fn arity(formals: &Formals) -> Rc<Value> {
+        let (min, max) = formals.arity();
+        let max = match max {
+            Some(max) => Primitive::NUMBER(max as i64),
+            None => Primitive::BOOLEAN(false),
+        };
+        Rc::new(Value::PAIR {
+            car: Rc::new(Value::PRIMITIVE(Rc::new(Primitive::NUMBER(min as i64)))),
+            cdr: Rc::new(Value::PRIMITIVE(Rc::new(max))),
+        })
+    }
+    if args.len() != 1 {
+        return None;
+    }
+    match &*args[0] {
+        Value::CLOSURE(clojure) => Some(arity(clojure.formals())),
+        Value::CASELAMBDA(clauses) => Some(vec_to_list(
+            clauses
+                .iter()
+                .map(|clause| arity(clause.formals()))
+                .collect(),
+        )),
+        Value::BUILTIN(_) => boolean(false),
+        // called with no arguments for the value
+        Value::PARAMETER(_) => Some(arity(&Formals {
+            parameters: Vec::new(),
+            rest: None,
+        })),
+        _ => panic!("procedure-arity: not a procedure"),
+    }
+}
+
+pub fn values(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() == 1 {
+        Some(args[0].clone())
+    } else {
+        Some(Rc::new(Value::VALUES(args)))
+    }
+}
+
+pub fn call_with_values(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let produced = apply(args[0].clone(), Vec::new())?;
+    let operands = match &*produced {
+        Value::VALUES(values) => values.clone(),
+        _ => vec![produced.clone()],
+    };
+    apply(args[1].clone(), operands)
+}
+
+// (make-parameter value [converter]), the converter also applies to the
+// values given by parameterize
+pub fn make_parameter(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let converter = args.get(1).cloned();
+    let value = match &converter {
+        Some(converter) => apply(converter.clone(), vec![args[0].clone()])?,
+        None => args[0].clone(),
+    };
+    Some(Rc::new(Value::PARAMETER(Rc::new(Parameter {
+        value: RefCell::new(value),
+        converter,
+    }))))
+}
+
+// (" parameterize" thunk param value ...), what parameterize expands to: the
+// parameters hold the converted values while the thunk runs
+pub fn parameterize(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    let (thunk, bindings) = args.split_first()?;
+    if bindings.len() % 2 != 0 {
+        return None;
+    }
+    let mut parameters = Vec::new();
+    for binding in bindings.chunks(2) {
+        let parameter = match &*binding[0] {
+            Value::PARAMETER(parameter) => parameter.clone(),
+            _ => panic!("parameterize: not a parameter"),
+        };
+        parameters.push((parameter, binding[1].clone()));
+    }
+    let mut converted = Vec::new();
+    for (parameter, value) in parameters {
+        let value = match &parameter.converter {
+            Some(converter) => apply(converter.clone(), vec![value])?,
+            None => value,
+        };
+        converted.push((parameter, value));
+    }
+    call_parameterized(converted, thunk.clone())
+}
+
+// Gives the parameters their old values back when dropped, so they come back
+// even when the code run in between fails or panics
+struct Restore(Vec<(Rc<Parameter>, Rc<Value>)>);
+
+impl Restore {
+    fn bind(bindings: Vec<(Rc<Parameter>, Rc<Value>)>) -> Self {
+        Restore(
+            bindings
+                .into_iter()
+                .map(|(parameter, value)| {
+                    let saved = parameter.value.replace(value);
+                    (parameter, saved)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        for (parameter, value) in self.0.drain(..) {
+            parameter.value.replace(value);
+        }
+    }
+}
+
+// Call the thunk with the parameters holding the given values
+fn call_parameterized(bindings: Vec<(Rc<Parameter>, Rc<Value>)>, thunk: Rc<Value>) -> Option<Rc<Value>> {
+    let _restore = Restore::bind(bindings);
+    apply(thunk, Vec::new())
+}
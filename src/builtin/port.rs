@@ -0,0 +1,933 @@
+use super::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Write};
+
+// Ports
+//
+// The current ports are parameter objects shared by every environment of the
+// thread, builtins fall back to their values when no port is passed
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("port?", if_port),
+        ("input-port?", if_input_port),
+        ("output-port?", if_output_port),
+        ("textual-port?", if_textual_port),
+        ("binary-port?", if_binary_port),
+        ("input-port-open?", if_input_port_open),
+        ("output-port-open?", if_output_port_open),
+        ("eof-object", eof_object),
+        ("eof-object?", if_eof_object),
+        ("read-char", read_char),
+        ("peek-char", peek_char),
+        ("read-line", read_line),
+        ("read-string", read_string),
+        ("char-ready?", char_ready),
+        ("read", read),
+        ("read-u8", read_u8),
+        ("peek-u8", peek_u8),
+        ("u8-ready?", u8_ready),
+        ("write-char", write_char),
+        ("write-string", write_string),
+        ("write-u8", write_u8),
+        ("newline", newline),
+        ("flush-output-port", flush_output_port),
+        ("display", display),
+        ("write", write),
+        // cycles are labelled all the same, so it doesn't loop on them
+        ("write-simple", write),
+        ("write-shared", write_shared),
+        ("open-input-file", open_input_file),
+        ("open-binary-input-file", open_binary_input_file),
+        ("open-output-file", open_output_file),
+        ("open-binary-output-file", open_binary_output_file),
+        ("call-with-port", call_with_port),
+        ("call-with-input-file", call_with_input_file),
+        ("call-with-output-file", call_with_output_file),
+        ("with-input-from-file", with_input_from_file),
+        ("with-output-to-file", with_output_to_file),
+        ("file-exists?", file_exists),
+        ("delete-file", delete_file),
+        ("open-input-string", open_input_string),
+        ("open-output-string", open_output_string),
+        ("get-output-string", get_output_string),
+        ("open-input-bytevector", open_input_bytevector),
+        ("open-output-bytevector", open_output_bytevector),
+        ("get-output-bytevector", get_output_bytevector),
+        ("close-port", close_port),
+        ("close-input-port", close_input_port),
+        ("close-output-port", close_output_port),
+    ]
+}
+
+pub fn get_constants() -> Vec<(&'static str, Rc<Value>)> {
+    vec![
+        ("current-input-port", CURRENT_INPUT_PORT.with(Rc::clone)),
+        ("current-output-port", CURRENT_OUTPUT_PORT.with(Rc::clone)),
+        ("current-error-port", CURRENT_ERROR_PORT.with(Rc::clone)),
+    ]
+}
+
+thread_local! {
+    pub static CURRENT_INPUT_PORT: Rc<Value> =
+        parameter(Port::input(Box::new(std::io::stdin()), true, true));
+    pub static CURRENT_OUTPUT_PORT: Rc<Value> =
+        parameter(Port::output(Output::WRITER(Box::new(std::io::stdout())), true));
+    pub static CURRENT_ERROR_PORT: Rc<Value> =
+        parameter(Port::output(Output::WRITER(Box::new(std::io::stderr())), true));
+}
+
+// Ports a host installs in place of the console ones, None keeps the console
+// port of that direction
+#[derive(Default)]
+pub struct Ports {
+    pub input: Option<Rc<Value>>,
+    pub output: Option<Rc<Value>>,
+    pub error: Option<Rc<Value>>,
+}
+
+impl Ports {
+    // Run `f` with these ports as the current ones
+    pub fn install<T>(&self, f: impl FnOnce() -> T) -> T {
+        let bindings = [
+            (&CURRENT_INPUT_PORT, &self.input),
+            (&CURRENT_OUTPUT_PORT, &self.output),
+            (&CURRENT_ERROR_PORT, &self.error),
+        ]
+        .into_iter()
+        .filter_map(|(key, port)| Some((current_parameter(key), port.clone()?)))
+        .collect();
+        let _restore = Restore::bind(bindings);
+        f()
+    }
+}
+
+fn parameter(port: Port) -> Rc<Value> {
+    Rc::new(Value::PARAMETER(Rc::new(Parameter {
+        value: RefCell::new(Rc::new(Value::PORT(port))),
+        converter: None,
+    })))
+}
+
+fn current_parameter(port: &'static std::thread::LocalKey<Rc<Value>>) -> Rc<Parameter> {
+    port.with(|parameter| match &**parameter {
+        Value::PARAMETER(parameter) => parameter.clone(),
+        _ => unreachable!(),
+    })
+}
+
+// The value of one of the current port parameters
+fn current(port: &'static std::thread::LocalKey<Rc<Value>>) -> Rc<Value> {
+    let value = current_parameter(port).value.borrow().clone();
+    value
+}
+
+impl Port {
+    pub fn input(source: Box<dyn Read>, textual: bool, interactive: bool) -> Self {
+        Port {
+            direction: Direction::INPUT,
+            textual,
+            input: RefCell::new(Some(Input {
+                source,
+                lookahead: VecDeque::new(),
+                interactive,
+            })),
+            output: RefCell::new(None),
+        }
+    }
+
+    pub fn close_input(&self) {
+        self.input.replace(None);
+    }
+
+    // Buffered file output is flushed, so closing releases the file completely
+    pub fn close_output(&self) {
+        if let Some(mut output) = self.output.replace(None) {
+            output.flush("close-port");
+        }
+    }
+
+    pub fn output(output: Output, textual: bool) -> Self {
+        Port {
+            direction: Direction::OUTPUT,
+            textual,
+            input: RefCell::new(None),
+            output: RefCell::new(Some(output)),
+        }
+    }
+}
+
+impl Input {
+    // Make at least `n` bytes available unless the source ends first
+    fn fill(&mut self, n: usize) -> bool {
+        let mut buffer = [0; 4096];
+        while self.lookahead.len() < n {
+            match self.source.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(count) => self.lookahead.extend(&buffer[..count]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => panic!("read: {}", e),
+            }
+        }
+        true
+    }
+
+    fn peek_u8(&mut self) -> Option<u8> {
+        self.fill(1).then(|| self.lookahead[0])
+    }
+
+    fn read_u8(&mut self) -> Option<u8> {
+        self.fill(1).then(|| self.lookahead.pop_front()).flatten()
+    }
+
+    // Decode the next character, returning it with its length in bytes;
+    // malformed input decodes to U+FFFD one byte at a time
+    fn peek_char(&mut self) -> Option<(char, usize)> {
+        let first = self.peek_u8()?;
+        let width = match first {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Some((char::REPLACEMENT_CHARACTER, 1)),
+        };
+        self.fill(width);
+        let bytes: Vec<u8> = self.lookahead.iter().take(width).copied().collect();
+        match std::str::from_utf8(&bytes) {
+            Ok(decoded) => decoded.chars().next().map(|c| (c, width)),
+            Err(_) => Some((char::REPLACEMENT_CHARACTER, 1)),
+        }
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        let (c, width) = self.peek_char()?;
+        self.lookahead.drain(..width);
+        Some(c)
+    }
+
+    fn next_char(&mut self, name: &str) -> char {
+        self.read_char()
+            .unwrap_or_else(|| panic!("{}: unexpected end of input", name))
+    }
+
+    fn peek_is(&mut self, c: char) -> bool {
+        matches!(self.peek_char(), Some((next, _)) if next == c)
+    }
+
+    // Whitespace and comments between data
+    fn skip_atmosphere(&mut self) {
+        while let Some((c, _)) = self.peek_char() {
+            if c == ';' {
+                while !matches!(self.read_char(), None | Some('\n')) {}
+            } else if c.is_whitespace() {
+                self.read_char();
+            } else {
+                return;
+            }
+        }
+    }
+
+    // Take the characters of the next datum into `text`, reading no further
+    // than its end so the rest of the input stays for later reads
+    fn scan_datum(&mut self, text: &mut String) -> Option<()> {
+        let (c, _) = self.peek_char()?;
+        match c {
+            '\'' | '`' | ',' => {
+                text.push(self.next_char("read"));
+                if c == ',' && self.peek_is('@') {
+                    text.push(self.next_char("read"));
+                }
+                self.skip_atmosphere();
+                self.scan_required(text);
+            }
+            '(' => self.scan_list(text),
+            ')' => {
+                self.read_char();
+                panic!("read: unexpected )");
+            }
+            '"' => {
+                text.push(self.next_char("read"));
+                loop {
+                    let c = self.next_char("read");
+                    text.push(c);
+                    match c {
+                        '"' => break,
+                        '\\' => text.push(self.next_char("read")),
+                        _ => (),
+                    }
+                }
+            }
+            '#' => {
+                text.push(self.next_char("read"));
+                if self.peek_is('(') {
+                    self.scan_list(text);
+                } else if self.peek_is('u') {
+                    self.scan_atom(text);
+                    self.scan_list(text);
+                } else {
+                    if self.peek_is('\\') {
+                        text.push(self.next_char("read"));
+                        text.push(self.next_char("read"));
+                    }
+                    self.scan_atom(text);
+                }
+            }
+            _ => self.scan_atom(text),
+        }
+        Some(())
+    }
+
+    fn scan_required(&mut self, text: &mut String) {
+        if self.scan_datum(text).is_none() {
+            panic!("read: unexpected end of input");
+        }
+    }
+
+    fn scan_list(&mut self, text: &mut String) {
+        text.push(self.next_char("read"));
+        loop {
+            self.skip_atmosphere();
+            if self.peek_is(')') {
+                text.push(self.next_char("read"));
+                return;
+            }
+            text.push(' ');
+            self.scan_required(text);
+        }
+    }
+
+    // Up to the next delimiter
+    fn scan_atom(&mut self, text: &mut String) {
+        while let Some((c, _)) = self.peek_char() {
+            if c.is_whitespace() || matches!(c, '(' | ')' | '"' | ';') {
+                return;
+            }
+            text.push(self.next_char("read"));
+        }
+    }
+
+    fn ready(&self) -> bool {
+        !self.lookahead.is_empty() || !self.interactive
+    }
+}
+
+impl Output {
+    fn write_bytes(&mut self, bytes: &[u8], name: &str) {
+        match self {
+            Output::WRITER(writer) => writer
+                .write_all(bytes)
+                .unwrap_or_else(|e| panic!("{}: {}", name, e)),
+            Output::BUFFER(buffer) => buffer.extend_from_slice(bytes),
+        }
+    }
+
+    fn flush(&mut self, name: &str) {
+        if let Output::WRITER(writer) = self {
+            writer.flush().unwrap_or_else(|e| panic!("{}: {}", name, e));
+        }
+    }
+}
+
+fn to_port<'a>(value: &'a Value, name: &str) -> &'a Port {
+    match value {
+        Value::PORT(port) => port,
+        _ => panic!("{}: not a port", name),
+    }
+}
+
+// Run `f` on the open input side of the port at `index` of `args`, or of the
+// current input port
+fn with_input<T>(
+    args: &[Rc<Value>],
+    index: usize,
+    textual: bool,
+    name: &str,
+    f: impl FnOnce(&mut Input) -> T,
+) -> T {
+    let port = args
+        .get(index)
+        .cloned()
+        .unwrap_or_else(|| current(&CURRENT_INPUT_PORT));
+    let port = to_port(&port, name);
+    if port.textual != textual {
+        panic!("{}: not a {} port", name, if textual { "textual" } else { "binary" });
+    }
+    let mut input = port.input.borrow_mut();
+    match &mut *input {
+        Some(input) => f(input),
+        None => panic!("{}: not an open input port", name),
+    }
+}
+
+fn with_output<T>(
+    args: &[Rc<Value>],
+    index: usize,
+    textual: bool,
+    name: &str,
+    f: impl FnOnce(&mut Output) -> T,
+) -> T {
+    let port = args
+        .get(index)
+        .cloned()
+        .unwrap_or_else(|| current(&CURRENT_OUTPUT_PORT));
+    let port = to_port(&port, name);
+    if port.textual != textual {
+        panic!("{}: not a {} port", name, if textual { "textual" } else { "binary" });
+    }
+    let mut output = port.output.borrow_mut();
+    match &mut *output {
+        Some(output) => f(output),
+        None => panic!("{}: not an open output port", name),
+    }
+}
+
+fn eof() -> Option<Rc<Value>> {
+    Some(Rc::new(Value::EOF))
+}
+
+fn unspecified() -> Option<Rc<Value>> {
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+// External representations
+//
+// `display` writes strings and characters as they are, `write` as literals
+// that read back as the same datum
+
+fn char_literal(c: char) -> String {
+    match c {
+        '\u{7}' => "#\\alarm".to_string(),
+        '\u{8}' => "#\\backspace".to_string(),
+        '\u{7f}' => "#\\delete".to_string(),
+        '\u{1b}' => "#\\escape".to_string(),
+        '\n' => "#\\newline".to_string(),
+        '\0' => "#\\null".to_string(),
+        '\r' => "#\\return".to_string(),
+        ' ' => "#\\space".to_string(),
+        '\t' => "#\\tab".to_string(),
+        c if c.is_control() => format!("#\\x{:x}", c as u32),
+        c => format!("#\\{}", c),
+    }
+}
+
+fn string_literal(string: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            '\r' => literal.push_str("\\r"),
+            '\u{7}' => literal.push_str("\\a"),
+            '\u{8}' => literal.push_str("\\b"),
+            c if c.is_control() => literal.push_str(&format!("\\x{:x};", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// With `shared` every object reached more than once gets a datum label, not
+// only those in a cycle
+pub fn external(value: &Value, write: bool, shared: bool) -> String {
+    print(value, shared, &|value| match value {
+        Value::PRIMITIVE(primitive) => match &**primitive {
+            Primitive::BOOLEAN(true) => "#t".to_string(),
+            Primitive::BOOLEAN(false) => "#f".to_string(),
+            Primitive::CHAR(c) if write => char_literal(*c),
+            Primitive::STRING(string) if write => string_literal(&string.borrow()),
+            _ => primitive.to_string(),
+        },
+        _ => value.to_string(),
+    })
+}
+
+// Predicates
+
+pub fn if_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::PORT(_)))
+}
+
+macro_rules! port_predicate {
+    ($args:expr, |$port:ident| $test:expr) => {{
+        if $args.len() != 1 {
+            return None;
+        }
+        match &*$args[0] {
+            Value::PORT($port) => boolean($test),
+            _ => boolean(false),
+        }
+    }};
+}
+
+pub fn if_input_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    port_predicate!(args, |port| port.direction == Direction::INPUT)
+}
+
+pub fn if_output_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    port_predicate!(args, |port| port.direction == Direction::OUTPUT)
+}
+
+pub fn if_textual_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    port_predicate!(args, |port| port.textual)
+}
+
+pub fn if_binary_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    port_predicate!(args, |port| !port.textual)
+}
+
+pub fn if_input_port_open(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    port_predicate!(args, |port| port.input.borrow().is_some())
+}
+
+pub fn if_output_port_open(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    port_predicate!(args, |port| port.output.borrow().is_some())
+}
+
+pub fn eof_object(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if !args.is_empty() {
+        return None;
+    }
+    eof()
+}
+
+pub fn if_eof_object(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::EOF))
+}
+
+// Input
+
+pub fn read_char(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    match with_input(&args, 0, true, "read-char", Input::read_char) {
+        Some(c) => primitive(Primitive::CHAR(c)),
+        None => eof(),
+    }
+}
+
+pub fn peek_char(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    match with_input(&args, 0, true, "peek-char", Input::peek_char) {
+        Some((c, _)) => primitive(Primitive::CHAR(c)),
+        None => eof(),
+    }
+}
+
+pub fn read_line(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    let line = with_input(&args, 0, true, "read-line", |input| {
+        let mut line = String::new();
+        loop {
+            match input.read_char() {
+                Some('\n') => return Some(line),
+                Some(c) => line.push(c),
+                None if line.is_empty() => return None,
+                None => return Some(line),
+            }
+        }
+    });
+    match line {
+        Some(line) => primitive(Primitive::STRING(RefCell::new(line))),
+        None => eof(),
+    }
+}
+
+// (read-string k [port])
+pub fn read_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let k = to_index(&args[0], "read-string");
+    let string = with_input(&args, 1, true, "read-string", |input| {
+        let mut string = String::new();
+        while string.chars().count() < k {
+            match input.read_char() {
+                Some(c) => string.push(c),
+                None => break,
+            }
+        }
+        string
+    });
+    if string.is_empty() && k > 0 {
+        return eof();
+    }
+    primitive(Primitive::STRING(RefCell::new(string)))
+}
+
+pub fn char_ready(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    boolean(with_input(&args, 0, true, "char-ready?", |input| input.ready()))
+}
+
+// Data are read with the lexical syntax of programs
+pub fn read(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    let text = with_input(&args, 0, true, "read", |input| {
+        input.skip_atmosphere();
+        let mut text = String::new();
+        input.scan_datum(&mut text).map(|_| text)
+    });
+    match text {
+        Some(text) => {
+            let datum = parse_datum(&text).unwrap_or_else(|| panic!("read: bad syntax {}", text));
+            quote(datum)
+        }
+        None => eof(),
+    }
+}
+
+pub fn read_u8(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    match with_input(&args, 0, false, "read-u8", Input::read_u8) {
+        Some(byte) => primitive(Primitive::NUMBER(byte as i64)),
+        None => eof(),
+    }
+}
+
+pub fn peek_u8(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    match with_input(&args, 0, false, "peek-u8", Input::peek_u8) {
+        Some(byte) => primitive(Primitive::NUMBER(byte as i64)),
+        None => eof(),
+    }
+}
+
+pub fn u8_ready(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    boolean(with_input(&args, 0, false, "u8-ready?", |input| input.ready()))
+}
+
+// Output
+
+pub fn write_char(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let c = to_char(&args[0], "write-char");
+    with_output(&args, 1, true, "write-char", |output| {
+        output.write_bytes(c.to_string().as_bytes(), "write-char")
+    });
+    unspecified()
+}
+
+// (write-string string [port [start [end]]])
+pub fn write_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 4 {
+        return None;
+    }
+    let string = to_string(&args[0], "write-string");
+    let chars: Vec<char> = string.chars().collect();
+    let (start, end) = range_args(&args, 2, chars.len(), "write-string");
+    let string: String = chars[start..end].iter().collect();
+    with_output(&args, 1, true, "write-string", |output| {
+        output.write_bytes(string.as_bytes(), "write-string")
+    });
+    unspecified()
+}
+
+pub fn write_u8(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let byte = u8::try_from(to_integer(&args[0], "write-u8"))
+        .unwrap_or_else(|_| panic!("write-u8: not a byte"));
+    with_output(&args, 1, false, "write-u8", |output| {
+        output.write_bytes(&[byte], "write-u8")
+    });
+    unspecified()
+}
+
+pub fn newline(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    with_output(&args, 0, true, "newline", |output| {
+        output.write_bytes(b"\n", "newline")
+    });
+    unspecified()
+}
+
+pub fn flush_output_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() > 1 {
+        return None;
+    }
+    let port = args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| current(&CURRENT_OUTPUT_PORT));
+    if let Some(output) = &mut *to_port(&port, "flush-output-port").output.borrow_mut() {
+        output.flush("flush-output-port");
+    }
+    unspecified()
+}
+
+pub fn display(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let text = external(&args[0], false, false);
+    with_output(&args, 1, true, "display", |output| {
+        output.write_bytes(text.as_bytes(), "display")
+    });
+    unspecified()
+}
+
+fn write_with(args: &[Rc<Value>], shared: bool, name: &str) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let text = external(&args[0], true, shared);
+    with_output(args, 1, true, name, |output| output.write_bytes(text.as_bytes(), name));
+    unspecified()
+}
+
+pub fn write(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    write_with(&args, false, "write")
+}
+
+pub fn write_shared(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    write_with(&args, true, "write-shared")
+}
+
+// File ports
+
+fn open_input(args: &[Rc<Value>], textual: bool, name: &str) -> Port {
+    let path = to_string(&args[0], name);
+    let file = File::open(&path).unwrap_or_else(|e| panic!("{}: {}: {}", name, path, e));
+    Port::input(Box::new(BufReader::new(file)), textual, false)
+}
+
+fn open_output(args: &[Rc<Value>], textual: bool, name: &str) -> Port {
+    let path = to_string(&args[0], name);
+    let file = File::create(&path).unwrap_or_else(|e| panic!("{}: {}: {}", name, path, e));
+    Port::output(Output::WRITER(Box::new(BufWriter::new(file))), textual)
+}
+
+pub fn open_input_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(Rc::new(Value::PORT(open_input(&args, true, "open-input-file"))))
+}
+
+pub fn open_binary_input_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(Rc::new(Value::PORT(open_input(&args, false, "open-binary-input-file"))))
+}
+
+pub fn open_output_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(Rc::new(Value::PORT(open_output(&args, true, "open-output-file"))))
+}
+
+pub fn open_binary_output_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(Rc::new(Value::PORT(open_output(&args, false, "open-binary-output-file"))))
+}
+
+// Call the procedure with the port and close the port once it returns
+fn call_closing(port: Rc<Value>, procedure: Rc<Value>) -> Option<Rc<Value>> {
+    let result = apply(procedure, vec![port.clone()]);
+    close(&port, true, true, "call-with-port");
+    result
+}
+
+// Call the thunk with the port as the current input or output port, then
+// close the port
+fn with_current(
+    key: &'static std::thread::LocalKey<Rc<Value>>,
+    port: Rc<Value>,
+    thunk: Rc<Value>,
+) -> Option<Rc<Value>> {
+    let result = call_parameterized(vec![(current_parameter(key), port.clone())], thunk);
+    close(&port, true, true, "with-file");
+    result
+}
+
+pub fn call_with_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    to_port(&args[0], "call-with-port");
+    call_closing(args[0].clone(), args[1].clone())
+}
+
+pub fn call_with_input_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let port = Rc::new(Value::PORT(open_input(&args, true, "call-with-input-file")));
+    call_closing(port, args[1].clone())
+}
+
+pub fn call_with_output_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let port = Rc::new(Value::PORT(open_output(&args, true, "call-with-output-file")));
+    call_closing(port, args[1].clone())
+}
+
+pub fn with_input_from_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let port = Rc::new(Value::PORT(open_input(&args, true, "with-input-from-file")));
+    with_current(&CURRENT_INPUT_PORT, port, args[1].clone())
+}
+
+pub fn with_output_to_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let port = Rc::new(Value::PORT(open_output(&args, true, "with-output-to-file")));
+    with_current(&CURRENT_OUTPUT_PORT, port, args[1].clone())
+}
+
+pub fn file_exists(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(std::path::Path::new(&to_string(&args[0], "file-exists?")).exists())
+}
+
+pub fn delete_file(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let path = to_string(&args[0], "delete-file");
+    std::fs::remove_file(&path).unwrap_or_else(|e| panic!("delete-file: {}: {}", path, e));
+    unspecified()
+}
+
+// String and bytevector ports
+
+pub fn open_input_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let bytes = to_string(&args[0], "open-input-string").into_bytes();
+    Some(Rc::new(Value::PORT(Port::input(Box::new(Cursor::new(bytes)), true, false))))
+}
+
+pub fn open_output_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if !args.is_empty() {
+        return None;
+    }
+    Some(Rc::new(Value::PORT(Port::output(Output::BUFFER(Vec::new()), true))))
+}
+
+// What has been written to a string or bytevector port so far
+fn output_buffer(value: &Value, textual: bool, name: &str) -> Vec<u8> {
+    let port = to_port(value, name);
+    match &*port.output.borrow() {
+        Some(Output::BUFFER(buffer)) if port.textual == textual => buffer.clone(),
+        _ => panic!(
+            "{}: not an open {} port",
+            name,
+            if textual { "string" } else { "bytevector" }
+        ),
+    }
+}
+
+pub fn get_output_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let bytes = output_buffer(&args[0], true, "get-output-string");
+    primitive(Primitive::STRING(RefCell::new(
+        String::from_utf8_lossy(&bytes).into_owned(),
+    )))
+}
+
+pub fn open_input_bytevector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let bytes = match &*args[0] {
+        Value::BYTEVECTOR(bytes) => bytes.borrow().clone(),
+        _ => panic!("open-input-bytevector: not a bytevector"),
+    };
+    Some(Rc::new(Value::PORT(Port::input(Box::new(Cursor::new(bytes)), false, false))))
+}
+
+pub fn open_output_bytevector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if !args.is_empty() {
+        return None;
+    }
+    Some(Rc::new(Value::PORT(Port::output(Output::BUFFER(Vec::new()), false))))
+}
+
+pub fn get_output_bytevector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let bytes = output_buffer(&args[0], false, "get-output-bytevector");
+    Some(Rc::new(Value::BYTEVECTOR(RefCell::new(bytes))))
+}
+
+// Closing
+
+fn close(value: &Value, input: bool, output: bool, name: &str) -> Option<Rc<Value>> {
+    let port = to_port(value, name);
+    if input {
+        port.close_input();
+    }
+    if output {
+        port.close_output();
+    }
+    unspecified()
+}
+
+pub fn close_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    close(&args[0], true, true, "close-port")
+}
+
+pub fn close_input_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    close(&args[0], true, false, "close-input-port")
+}
+
+pub fn close_output_port(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    close(&args[0], false, true, "close-output-port")
+}
@@ -0,0 +1,36 @@
+; Prelude
+;
+; Standard procedures written in Scheme, evaluated once over the builtins and
+; shared by every initialized environment. A procedure is defined either here
+; or in Rust, callers can't tell which.
+
+; Numbers
+(define (<= a b) (not (> a b)))
+(define (>= a b) (not (< a b)))
+(define (zero? x) (= x 0))
+(define (positive? x) (> x 0))
+(define (negative? x) (< x 0))
+(define (abs x) (if (< x 0) (- 0 x) x))
+(define (square x) (* x x))
+(define (max x . rest)
+  (let loop ((m x) (rest rest))
+    (if (null? rest)
+        m
+        (loop (if (> (car rest) m) (car rest) m) (cdr rest)))))
+(define (min x . rest)
+  (let loop ((m x) (rest rest))
+    (if (null? rest)
+        m
+        (loop (if (< (car rest) m) (car rest) m) (cdr rest)))))
+
+; Booleans and symbols
+(define (boolean=? a b) (eq? a b))
+(define (symbol=? a b) (eq? a b))
+
+; Control
+; Without continuations the thunk can only leave normally
+(define (dynamic-wind before thunk after)
+  (before)
+  (let ((result (thunk)))
+    (after)
+    result))
@@ -0,0 +1,99 @@
+use super::*;
+use crate::interpreter::apply;
+use std::cell::RefCell;
+
+// Promises
+//
+// `delay` and `delay-force` expand into internal builtins of the same names
+// applied to a thunk of their expression
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        (" delay", delay),
+        (" delay-force", delay_force),
+        ("make-promise", make_promise),
+        ("promise?", if_promise),
+        ("force", force),
+    ]
+}
+
+pub fn new_promise(promise: Promise) -> Rc<Value> {
+    Rc::new(Value::PROMISE(RefCell::new(Rc::new(RefCell::new(promise)))))
+}
+
+// Force `value` if it is a promise, other values are returned as they are.
+// The result of a `delay-force` thunk takes over the promise being forced
+// instead of being forced recursively, so chains of them run in constant space.
+pub fn force_value(value: &Rc<Value>) -> Option<Rc<Value>> {
+    let Value::PROMISE(promise) = &**value else {
+        return Some(value.clone());
+    };
+    loop {
+        let state = promise.borrow().clone();
+        let (thunk, lazy) = match &*state.borrow() {
+            Promise::DONE(value) => return Some(value.clone()),
+            Promise::DELAYED { thunk, lazy } => (thunk.clone(), *lazy),
+        };
+        let result = apply(thunk, Vec::new())?;
+        // the thunk may have forced this very promise already
+        if matches!(*state.borrow(), Promise::DONE(_)) {
+            continue;
+        }
+        if !lazy {
+            *state.borrow_mut() = Promise::DONE(result);
+            continue;
+        }
+        match &*result {
+            Value::PROMISE(next) => {
+                let content = next.borrow().borrow().clone();
+                *state.borrow_mut() = content;
+                *next.borrow_mut() = state.clone();
+            }
+            _ => panic!("force: delay-force expression is not a promise"),
+        }
+    }
+}
+
+pub fn delay(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(new_promise(Promise::DELAYED {
+        thunk: args[0].clone(),
+        lazy: false,
+    }))
+}
+
+pub fn delay_force(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    Some(new_promise(Promise::DELAYED {
+        thunk: args[0].clone(),
+        lazy: true,
+    }))
+}
+
+pub fn make_promise(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    match &*args[0] {
+        Value::PROMISE(_) => Some(args[0].clone()),
+        _ => Some(new_promise(Promise::DONE(args[0].clone()))),
+    }
+}
+
+pub fn if_promise(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::PROMISE(_)))
+}
+
+pub fn force(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    force_value(&args[0])
+}
@@ -0,0 +1,102 @@
+use super::*;
+use std::cell::RefCell;
+
+// Records
+//
+// `define-record-type` expands into procedures calling these under internal
+// names, the type descriptor is passed along so instances of other types are
+// rejected
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        (" record-type", record_type),
+        (" record", record),
+        (" record?", if_record),
+        (" record-ref", record_ref),
+        (" record-set!", record_set),
+    ]
+}
+
+fn to_symbol(value: &Value, name: &str) -> String {
+    match value {
+        Value::PRIMITIVE(primitive) => match &**primitive {
+            Primitive::SYMBOL(symbol) => symbol.clone(),
+            _ => panic!("{}: not a symbol", name),
+        },
+        _ => panic!("{}: not a symbol", name),
+    }
+}
+
+fn to_record_type(value: &Value, name: &str) -> Rc<RecordType> {
+    match value {
+        Value::RECORDTYPE(rtd) => rtd.clone(),
+        _ => panic!("{}: not a record type", name),
+    }
+}
+
+// The fields of `value`, which must be an instance of `rtd`
+fn to_fields<'a>(value: &'a Value, rtd: &Rc<RecordType>, name: &str) -> &'a RefCell<Vec<Rc<Value>>> {
+    match value {
+        Value::RECORD { rtd: type_, fields } if Rc::ptr_eq(type_, rtd) => fields,
+        _ => panic!("{}: not a {}", name, rtd.name),
+    }
+}
+
+// (record-type 'name '(field ...))
+pub fn record_type(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let name = to_symbol(&args[0], "define-record-type");
+    let fields = list_to_vec(&args[1], "define-record-type")
+        .iter()
+        .map(|field| to_symbol(field, "define-record-type"))
+        .collect();
+    Some(Rc::new(Value::RECORDTYPE(Rc::new(RecordType { name, fields }))))
+}
+
+// (record rtd value ...) with a value for every field
+pub fn record(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    let (rtd, values) = args.split_first()?;
+    let rtd = to_record_type(rtd, "record constructor");
+    if values.len() != rtd.fields.len() {
+        return None;
+    }
+    Some(Rc::new(Value::RECORD {
+        rtd,
+        fields: RefCell::new(values.to_vec()),
+    }))
+}
+
+// (record? obj rtd)
+pub fn if_record(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let rtd = to_record_type(&args[1], "record predicate");
+    boolean(matches!(&*args[0], Value::RECORD { rtd: type_, .. } if Rc::ptr_eq(type_, &rtd)))
+}
+
+// (record-ref obj rtd index)
+pub fn record_ref(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 3 {
+        return None;
+    }
+    let rtd = to_record_type(&args[1], "record accessor");
+    let index = to_index(&args[2], "record accessor");
+    let fields = to_fields(&args[0], &rtd, &rtd.fields[index]);
+    let value = fields.borrow()[index].clone();
+    Some(value)
+}
+
+// (record-set! obj rtd index value)
+pub fn record_set(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 4 {
+        return None;
+    }
+    let rtd = to_record_type(&args[1], "record modifier");
+    let index = to_index(&args[2], "record modifier");
+    let fields = to_fields(&args[0], &rtd, &rtd.fields[index]);
+    fields.borrow_mut()[index] = args[3].clone();
+    Some(Rc::new(Value::UNSPECIFIED))
+}
@@ -0,0 +1,134 @@
+use super::promise::{force_value, new_promise};
+use super::*;
+
+// Streams
+//
+// A stream is a promise of either the empty list or a pair of a promise of
+// the first element and the stream of the rest, `stream-cons` is syntax that
+// delays both of its operands
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("stream?", if_stream),
+        ("stream-null?", if_stream_null),
+        ("stream-pair?", if_stream_pair),
+        ("stream-car", stream_car),
+        ("stream-cdr", stream_cdr),
+        ("stream-take", stream_take),
+        ("stream->list", stream_to_list),
+        ("list->stream", list_to_stream),
+    ]
+}
+
+pub fn get_constants() -> Vec<(&'static str, Rc<Value>)> {
+    vec![("stream-null", new_promise(Promise::DONE(Value::nil())))]
+}
+
+fn to_stream(value: &Rc<Value>, name: &str) -> Option<Rc<Value>> {
+    match &**value {
+        Value::PROMISE(_) => force_value(value),
+        _ => panic!("{}: not a stream", name),
+    }
+}
+
+// The promise of the first element and the rest of a non-empty stream
+fn to_stream_pair(value: &Rc<Value>, name: &str) -> Option<(Rc<Value>, Rc<Value>)> {
+    match &*to_stream(value, name)? {
+        Value::PAIR { car, cdr } => Some((car.clone(), cdr.clone())),
+        _ => panic!("{}: not a stream pair", name),
+    }
+}
+
+fn stream_pair(car: Rc<Value>, cdr: Rc<Value>) -> Rc<Value> {
+    new_promise(Promise::DONE(Rc::new(Value::PAIR { car, cdr })))
+}
+
+pub fn if_stream(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::PROMISE(_)))
+}
+
+pub fn if_stream_null(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    match &*args[0] {
+        Value::PROMISE(_) => boolean(force_value(&args[0])?.is_nil()),
+        _ => boolean(false),
+    }
+}
+
+pub fn if_stream_pair(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    match &*args[0] {
+        Value::PROMISE(_) => boolean(matches!(*force_value(&args[0])?, Value::PAIR { .. })),
+        _ => boolean(false),
+    }
+}
+
+pub fn stream_car(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let (car, _) = to_stream_pair(&args[0], "stream-car")?;
+    force_value(&car)
+}
+
+pub fn stream_cdr(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let (_, cdr) = to_stream_pair(&args[0], "stream-cdr")?;
+    Some(cdr)
+}
+
+// The stream of the first n elements; the spine is walked right away, the
+// elements themselves stay delayed
+pub fn stream_take(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let n = to_index(&args[0], "stream-take");
+    let mut elements = Vec::new();
+    let mut stream = args[1].clone();
+    while elements.len() < n && !to_stream(&stream, "stream-take")?.is_nil() {
+        let (car, cdr) = to_stream_pair(&stream, "stream-take")?;
+        elements.push(car);
+        stream = cdr;
+    }
+    let null = new_promise(Promise::DONE(Value::nil()));
+    Some(elements.into_iter().rev().fold(null, |cdr, car| stream_pair(car, cdr)))
+}
+
+// (stream->list stream [n])
+pub fn stream_to_list(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let n = args.get(1).map(|n| to_index(n, "stream->list"));
+    let mut values = Vec::new();
+    let mut stream = args[0].clone();
+    while n.is_none_or(|n| values.len() < n) && !to_stream(&stream, "stream->list")?.is_nil() {
+        let (car, cdr) = to_stream_pair(&stream, "stream->list")?;
+        values.push(force_value(&car)?);
+        stream = cdr;
+    }
+    Some(vec_to_list(values))
+}
+
+pub fn list_to_stream(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let null = new_promise(Promise::DONE(Value::nil()));
+    Some(
+        list_to_vec(&args[0], "list->stream")
+            .into_iter()
+            .rev()
+            .fold(null, |cdr, car| stream_pair(new_promise(Promise::DONE(car)), cdr)),
+    )
+}
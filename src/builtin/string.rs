@@ -0,0 +1,281 @@
+use super::*;
+use crate::interpreter::apply;
+use std::cell::RefCell;
+
+// Strings
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("string?", if_string),
+        ("make-string", make_string),
+        ("string", string),
+        ("string-length", string_length),
+        ("string-ref", string_ref),
+        ("string-set!", string_set),
+        ("substring", substring),
+        ("string-append", string_append),
+        ("string-copy", string_copy),
+        ("string-copy!", string_copy_to),
+        ("string-fill!", string_fill),
+        ("string->list", string_to_list),
+        ("list->string", list_to_string),
+        ("string=?", string_eq),
+        ("string<?", string_lt),
+        ("string>?", string_gt),
+        ("string<=?", string_le),
+        ("string>=?", string_ge),
+        ("string-ci=?", string_ci_eq),
+        ("string-ci<?", string_ci_lt),
+        ("string-ci>?", string_ci_gt),
+        ("string-ci<=?", string_ci_le),
+        ("string-ci>=?", string_ci_ge),
+        ("string-upcase", string_upcase),
+        ("string-downcase", string_downcase),
+        ("string-foldcase", string_foldcase),
+        ("string-for-each", string_for_each),
+        ("string-map", string_map),
+    ]
+}
+
+fn new_string(string: String) -> Option<Rc<Value>> {
+    primitive(Primitive::STRING(RefCell::new(string)))
+}
+
+pub fn if_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    match &*args[0] {
+        Value::PRIMITIVE(primitive) => boolean(matches!(**primitive, Primitive::STRING(_))),
+        _ => boolean(false),
+    }
+}
+
+pub fn make_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let k = usize::try_from(to_integer(&args[0], "make-string")).ok()?;
+    let fill = args.get(1).map_or(' ', |c| to_char(c, "make-string"));
+    limits::check(k.saturating_mul(fill.len_utf8()));
+    new_string(std::iter::repeat_n(fill, k).collect())
+}
+
+pub fn string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    new_string(args.iter().map(|c| to_char(c, "string")).collect())
+}
+
+pub fn string_length(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let length = to_str_cell(&args[0], "string-length").borrow().chars().count();
+    primitive(Primitive::NUMBER(length as i64))
+}
+
+pub fn string_ref(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let string = to_str_cell(&args[0], "string-ref").borrow();
+    let k = to_integer(&args[1], "string-ref");
+    match usize::try_from(k).ok().and_then(|k| string.chars().nth(k)) {
+        Some(c) => primitive(Primitive::CHAR(c)),
+        None => panic!("string-ref: index {} out of range", k),
+    }
+}
+
+pub fn string_set(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 3 {
+        return None;
+    }
+    let mut string = to_str_cell(&args[0], "string-set!").borrow_mut();
+    let k = to_integer(&args[1], "string-set!");
+    let c = to_char(&args[2], "string-set!");
+    match usize::try_from(k).ok().and_then(|k| string.char_indices().nth(k)) {
+        Some((i, old)) => string.replace_range(i..i + old.len_utf8(), c.encode_utf8(&mut [0; 4])),
+        None => panic!("string-set!: index {} out of range", k),
+    }
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn substring(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 3 {
+        return None;
+    }
+    string_copy(args)
+}
+
+pub fn string_append(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    new_string(args.iter().map(|s| to_string(s, "string-append")).collect())
+}
+
+pub fn string_copy(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let string = to_string(&args[0], "string-copy");
+    let (start, end) = range_args(&args, 1, string.chars().count(), "string-copy");
+    new_string(string.chars().skip(start).take(end - start).collect())
+}
+
+pub fn string_copy_to(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 3 || args.len() > 5 {
+        return None;
+    }
+    let from = to_string(&args[2], "string-copy!");
+    let (start, end) = range_args(&args, 3, from.chars().count(), "string-copy!");
+    let mut to: Vec<char> = to_string(&args[0], "string-copy!").chars().collect();
+    let at = usize::try_from(to_integer(&args[1], "string-copy!")).ok()?;
+    if at + (end - start) > to.len() {
+        panic!("string-copy!: not enough room in destination");
+    }
+    to.splice(at..at + (end - start), from.chars().skip(start).take(end - start));
+    *to_str_cell(&args[0], "string-copy!").borrow_mut() = to.into_iter().collect();
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn string_fill(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 || args.len() > 4 {
+        return None;
+    }
+    let fill = to_char(&args[1], "string-fill!");
+    let mut string = to_str_cell(&args[0], "string-fill!").borrow_mut();
+    let (start, end) = range_args(&args, 2, string.chars().count(), "string-fill!");
+    *string = string
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if (start..end).contains(&i) { fill } else { c })
+        .collect();
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn string_to_list(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let string = to_string(&args[0], "string->list");
+    let (start, end) = range_args(&args, 1, string.chars().count(), "string->list");
+    let chars = string.chars().skip(start).take(end - start);
+    Some(vec_to_list(
+        chars.map(|c| Rc::new(Value::PRIMITIVE(Rc::new(Primitive::CHAR(c))))).collect(),
+    ))
+}
+
+pub fn list_to_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let chars = list_to_vec(&args[0], "list->string");
+    new_string(chars.iter().map(|c| to_char(c, "list->string")).collect())
+}
+
+macro_rules! compare_strings {
+    ($args:expr, $name:expr, $key:expr, $op:tt) => {{
+        if $args.is_empty() {
+            return None;
+        }
+        let strings: Vec<String> = $args.iter().map(|s| $key(to_string(s, $name))).collect();
+        boolean(strings.windows(2).all(|w| w[0] $op w[1]))
+    }};
+}
+
+fn foldcase(string: String) -> String {
+    string.to_lowercase()
+}
+
+pub fn string_eq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string=?", |s| s, ==)
+}
+
+pub fn string_lt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string<?", |s| s, <)
+}
+
+pub fn string_gt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string>?", |s| s, >)
+}
+
+pub fn string_le(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string<=?", |s| s, <=)
+}
+
+pub fn string_ge(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string>=?", |s| s, >=)
+}
+
+pub fn string_ci_eq(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string-ci=?", foldcase, ==)
+}
+
+pub fn string_ci_lt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string-ci<?", foldcase, <)
+}
+
+pub fn string_ci_gt(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string-ci>?", foldcase, >)
+}
+
+pub fn string_ci_le(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string-ci<=?", foldcase, <=)
+}
+
+pub fn string_ci_ge(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    compare_strings!(args, "string-ci>=?", foldcase, >=)
+}
+
+pub fn string_upcase(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    new_string(to_string(&args[0], "string-upcase").to_uppercase())
+}
+
+pub fn string_downcase(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    new_string(to_string(&args[0], "string-downcase").to_lowercase())
+}
+
+pub fn string_foldcase(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    new_string(foldcase(to_string(&args[0], "string-foldcase")))
+}
+
+// Call `proc` on the i-th chars of all `strings`, up to the shortest one
+fn map_chars(args: &[Rc<Value>], name: &str) -> Option<Vec<Rc<Value>>> {
+    let proc = args[0].clone();
+    let strings: Vec<Vec<char>> = args[1..]
+        .iter()
+        .map(|s| to_string(s, name).chars().collect())
+        .collect();
+    let len = strings.iter().map(Vec::len).min()?;
+    (0..len)
+        .map(|i| {
+            let chars = strings
+                .iter()
+                .map(|s| Rc::new(Value::PRIMITIVE(Rc::new(Primitive::CHAR(s[i])))))
+                .collect();
+            apply(proc.clone(), chars)
+        })
+        .collect()
+}
+
+pub fn string_for_each(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    map_chars(&args, "string-for-each")?;
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn string_map(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    let chars = map_chars(&args, "string-map")?;
+    new_string(chars.iter().map(|c| to_char(c, "string-map")).collect())
+}
@@ -0,0 +1,210 @@
+use super::*;
+use crate::interpreter::apply;
+use std::cell::RefCell;
+
+// Vectors
+
+pub fn get_builtins() -> Vec<(&'static str, Builtin)> {
+    vec![
+        ("vector?", if_vector),
+        ("make-vector", make_vector),
+        ("vector", vector),
+        ("vector-length", vector_length),
+        ("vector-ref", vector_ref),
+        ("vector-set!", vector_set),
+        ("vector->list", vector_to_list),
+        ("list->vector", list_to_vector),
+        ("vector->string", vector_to_string),
+        ("string->vector", string_to_vector),
+        ("vector-copy", vector_copy),
+        ("vector-copy!", vector_copy_to),
+        ("vector-append", vector_append),
+        ("vector-fill!", vector_fill),
+        ("vector-map", vector_map),
+        ("vector-for-each", vector_for_each),
+        // what quasiquote expands to
+        (" list->vector", list_to_vector),
+    ]
+}
+
+fn new_vector(values: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    let vector = Rc::new(Value::VECTOR(RefCell::new(values)));
+    limits::charge_value(&vector);
+    Some(vector)
+}
+
+fn to_vector_cell<'a>(value: &'a Value, name: &str) -> &'a RefCell<Vec<Rc<Value>>> {
+    match value {
+        Value::VECTOR(values) => values,
+        _ => panic!("{}: not a vector", name),
+    }
+}
+
+fn to_vector(value: &Value, name: &str) -> Vec<Rc<Value>> {
+    to_vector_cell(value, name).borrow().clone()
+}
+
+pub fn if_vector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    boolean(matches!(*args[0], Value::VECTOR(_)))
+}
+
+pub fn make_vector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 2 {
+        return None;
+    }
+    let k = to_index(&args[0], "make-vector");
+    limits::check(k.saturating_mul(std::mem::size_of::<Rc<Value>>()));
+    let fill = args.get(1).cloned().unwrap_or_else(|| Rc::new(Value::UNSPECIFIED));
+    new_vector(vec![fill; k])
+}
+
+pub fn vector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    new_vector(args)
+}
+
+pub fn vector_length(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    let length = to_vector_cell(&args[0], "vector-length").borrow().len();
+    primitive(Primitive::NUMBER(length as i64))
+}
+
+pub fn vector_ref(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 2 {
+        return None;
+    }
+    let values = to_vector_cell(&args[0], "vector-ref").borrow();
+    let k = to_index(&args[1], "vector-ref");
+    match values.get(k) {
+        Some(value) => Some(value.clone()),
+        None => panic!("vector-ref: index {} out of range", k),
+    }
+}
+
+pub fn vector_set(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 3 {
+        return None;
+    }
+    let mut values = to_vector_cell(&args[0], "vector-set!").borrow_mut();
+    let k = to_index(&args[1], "vector-set!");
+    match values.get_mut(k) {
+        Some(value) => *value = args[2].clone(),
+        None => panic!("vector-set!: index {} out of range", k),
+    }
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn vector_to_list(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let values = to_vector(&args[0], "vector->list");
+    let (start, end) = range_args(&args, 1, values.len(), "vector->list");
+    Some(vec_to_list(values[start..end].to_vec()))
+}
+
+pub fn list_to_vector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() != 1 {
+        return None;
+    }
+    new_vector(list_to_vec(&args[0], "list->vector"))
+}
+
+pub fn vector_to_string(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let values = to_vector(&args[0], "vector->string");
+    let (start, end) = range_args(&args, 1, values.len(), "vector->string");
+    let string = values[start..end]
+        .iter()
+        .map(|c| to_char(c, "vector->string"))
+        .collect();
+    primitive(Primitive::STRING(RefCell::new(string)))
+}
+
+pub fn string_to_vector(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let chars: Vec<char> = to_string(&args[0], "string->vector").chars().collect();
+    let (start, end) = range_args(&args, 1, chars.len(), "string->vector");
+    new_vector(
+        chars[start..end]
+            .iter()
+            .map(|c| Rc::new(Value::PRIMITIVE(Rc::new(Primitive::CHAR(*c)))))
+            .collect(),
+    )
+}
+
+pub fn vector_copy(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.is_empty() || args.len() > 3 {
+        return None;
+    }
+    let values = to_vector(&args[0], "vector-copy");
+    let (start, end) = range_args(&args, 1, values.len(), "vector-copy");
+    new_vector(values[start..end].to_vec())
+}
+
+pub fn vector_copy_to(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 3 || args.len() > 5 {
+        return None;
+    }
+    // copy first, `to` and `from` may be the same vector
+    let from = to_vector(&args[2], "vector-copy!");
+    let (start, end) = range_args(&args, 3, from.len(), "vector-copy!");
+    let mut to = to_vector_cell(&args[0], "vector-copy!").borrow_mut();
+    let at = to_index(&args[1], "vector-copy!");
+    if at + (end - start) > to.len() {
+        panic!("vector-copy!: not enough room in destination");
+    }
+    to[at..at + (end - start)].clone_from_slice(&from[start..end]);
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+pub fn vector_append(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    new_vector(
+        args.iter()
+            .flat_map(|v| to_vector(v, "vector-append"))
+            .collect(),
+    )
+}
+
+pub fn vector_fill(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 || args.len() > 4 {
+        return None;
+    }
+    let mut values = to_vector_cell(&args[0], "vector-fill!").borrow_mut();
+    let (start, end) = range_args(&args, 2, values.len(), "vector-fill!");
+    values[start..end].fill(args[1].clone());
+    Some(Rc::new(Value::UNSPECIFIED))
+}
+
+// Call `proc` on the i-th elements of all `vectors`, up to the shortest one
+fn map_elements(args: &[Rc<Value>], name: &str) -> Option<Vec<Rc<Value>>> {
+    let proc = args[0].clone();
+    let vectors: Vec<Vec<Rc<Value>>> = args[1..].iter().map(|v| to_vector(v, name)).collect();
+    let len = vectors.iter().map(Vec::len).min()?;
+    (0..len)
+        .map(|i| apply(proc.clone(), vectors.iter().map(|v| v[i].clone()).collect()))
+        .collect()
+}
+
+pub fn vector_map(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    new_vector(map_elements(&args, "vector-map")?)
+}
+
+pub fn vector_for_each(args: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    if args.len() < 2 {
+        return None;
+    }
+    map_elements(&args, "vector-for-each")?;
+    Some(Rc::new(Value::UNSPECIFIED))
+}
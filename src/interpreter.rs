@@ -1,14 +1,21 @@
-use crate::builtin::get_builtins;
+use crate::builtin::{get_builtins, get_constants, PRELUDE};
+use crate::library;
+use crate::limits;
 use crate::parser::*;
+use crate::policy;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::VecDeque;
 use std::fmt::Display;
-use std::rc::Rc;
+use std::io::{Read, Write};
+use std::rc::{Rc, Weak};
 
 #[derive(Debug)]
 pub struct Env {
     parent: Option<Rc<RefCell<Env>>>,
     values: HashMap<String, Rc<Value>>,
+    // the names bound when sealed, which can't be defined again
+    sealed: HashSet<String>,
 }
 
 #[derive(Debug)]
@@ -17,27 +24,129 @@ pub struct Clojure {
     env: Rc<RefCell<Env>>,
 }
 
+// The descriptor `define-record-type` binds to the type name
+#[derive(Debug)]
+pub struct RecordType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+// The state of a promise, shared by the promises a `delay-force` chain forwards to
+#[derive(Debug, Clone)]
+pub enum Promise {
+    DONE(Rc<Value>),
+    // the thunk of `delay` returns the value, the one of `delay-force` another promise
+    DELAYED { thunk: Rc<Value>, lazy: bool },
+}
+
+// The equivalence a hash table compares its keys with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Equivalence {
+    EQ,
+    EQV,
+    EQUAL,
+    STRING,
+}
+
+pub type Buckets = HashMap<u64, Vec<(Rc<Value>, Rc<Value>)>>;
+
+// Entries are grouped by the hash of their key, hashing agrees with the
+// equivalence so equivalent keys always land in the same bucket
+#[derive(Debug)]
+pub struct HashTable {
+    pub equivalence: Equivalence,
+    pub buckets: Buckets,
+}
+
+// Bytes read ahead of the source are kept for peeking
+pub struct Input {
+    pub source: Box<dyn Read>,
+    pub lookahead: VecDeque<u8>,
+    // whether reading the source may block, which `char-ready?` can't see ahead of
+    pub interactive: bool,
+}
+
+pub enum Output {
+    WRITER(Box<dyn Write>),
+    // string and bytevector ports accumulate what is written
+    BUFFER(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    INPUT,
+    OUTPUT,
+}
+
+// Either side is None when the port isn't in that direction or has been closed
+pub struct Port {
+    pub direction: Direction,
+    pub textual: bool,
+    pub input: RefCell<Option<Input>>,
+    pub output: RefCell<Option<Output>>,
+}
+
+impl std::fmt::Debug for Port {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<port>")
+    }
+}
+
+// A parameter object, called with no arguments it returns its value
+#[derive(Debug)]
+pub struct Parameter {
+    pub value: RefCell<Rc<Value>>,
+    pub converter: Option<Rc<Value>>,
+}
+
+pub type Builtin = fn(Vec<Rc<Value>>) -> Option<Rc<Value>>;
+
 #[derive(Debug)]
 pub enum Value {
     PRIMITIVE(Rc<Primitive>),
     CLOSURE(Rc<Clojure>),
-    BUILTIN(fn(Vec<Rc<Value>>) -> Option<Rc<Value>>),
+    CASELAMBDA(Vec<Rc<Clojure>>),
+    BUILTIN(Builtin),
+    UNSPECIFIED,
     PAIR {
-        car: Option<Rc<Value>>,
-        cdr: Option<Rc<Value>>,
+        car: Rc<Value>,
+        cdr: Rc<Value>,
+    },
+    VECTOR(RefCell<Vec<Rc<Value>>>),
+    BYTEVECTOR(RefCell<Vec<u8>>),
+    RECORDTYPE(Rc<RecordType>),
+    RECORD {
+        rtd: Rc<RecordType>,
+        fields: RefCell<Vec<Rc<Value>>>,
     },
+    PROMISE(RefCell<Rc<RefCell<Promise>>>),
+    HASHTABLE(RefCell<HashTable>),
+    PORT(Port),
+    PARAMETER(Rc<Parameter>),
+    ENVIRONMENT(Rc<RefCell<Env>>),
+    EOF,
+    // The result of `(values ...)` with other than exactly one value
+    VALUES(Vec<Rc<Value>>),
 }
 
-impl PartialEq for Value {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Value::PRIMITIVE(d1), Value::PRIMITIVE(d2)) => d1 == d2,
-            (Value::CLOSURE(_), Value::CLOSURE(_)) => false,
-            (Value::BUILTIN(_), Value::BUILTIN(_)) => false,
-            (Value::PAIR { car: c1, cdr: d1 }, Value::PAIR { car: c2, cdr: d2 }) => {
-                c1 == c2 && d1 == d2
+// A list is dropped along its cdrs in a loop, so a long one doesn't overflow
+// the stack; each pair nothing else holds is unlinked from the rest first
+impl Drop for Value {
+    fn drop(&mut self) {
+        let Value::PAIR { cdr, .. } = self else {
+            return;
+        };
+        if !matches!(**cdr, Value::PAIR { .. }) {
+            return;
+        }
+        let mut next = std::mem::replace(cdr, Rc::new(Value::UNSPECIFIED));
+        while let Ok(mut pair) = Rc::try_unwrap(next) {
+            match &mut pair {
+                Value::PAIR { cdr, .. } if matches!(**cdr, Value::PAIR { .. }) => {
+                    next = std::mem::replace(cdr, Rc::new(Value::UNSPECIFIED));
+                }
+                _ => break,
             }
-            _ => false,
         }
     }
 }
@@ -47,8 +156,10 @@ impl PartialEq for Primitive {
         match (self, other) {
             (Primitive::BOOLEAN(b1), Primitive::BOOLEAN(b2)) => b1 == b2,
             (Primitive::NUMBER(i1), Primitive::NUMBER(i2)) => i1 == i2,
-            (Primitive::STRING(s1), Primitive::STRING(s2)) => s1 == s2,
+            (Primitive::CHAR(c1), Primitive::CHAR(c2)) => c1 == c2,
+            (Primitive::STRING(s1), Primitive::STRING(s2)) => *s1.borrow() == *s2.borrow(),
             (Primitive::SYMBOL(s1), Primitive::SYMBOL(s2)) => s1 == s2,
+            (Primitive::NIL, Primitive::NIL) => true,
             _ => false,
         }
     }
@@ -58,162 +169,691 @@ impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::PRIMITIVE(datum) => write!(f, "{}", datum),
-            Value::CLOSURE(clojure) => write!(f, "#<clojure {:?}>", clojure),
+            Value::CLOSURE(_) => write!(f, "#<procedure>"),
+            Value::CASELAMBDA(_) => write!(f, "#<case-lambda>"),
             Value::BUILTIN(_) => write!(f, "#<builtin>"),
-            Value::PAIR { car, cdr } => match (car, cdr) {
-                (Some(car), Some(cdr)) => write!(f, "({} . {})", car, cdr),
-                (Some(car), None) => write!(f, "({})", car),
-                (None, Some(cdr)) => write!(f, "({})", cdr),
-                (None, None) => write!(f, "()"),
-            },
+            Value::UNSPECIFIED => write!(f, "#<unspecified>"),
+            Value::PAIR { .. } | Value::VECTOR(_) | Value::RECORD { .. } | Value::VALUES(_) => {
+                write!(f, "{}", print(self, false, &|value| value.to_string()))
+            }
+            Value::BYTEVECTOR(bytes) => {
+                let bytes: Vec<String> = bytes.borrow().iter().map(|byte| byte.to_string()).collect();
+                write!(f, "#u8({})", bytes.join(" "))
+            }
+            Value::RECORDTYPE(rtd) => write!(f, "#<record-type {}>", rtd.name),
+            Value::PROMISE(_) => write!(f, "#<promise>"),
+            Value::HASHTABLE(_) => write!(f, "#<hash-table>"),
+            Value::PORT(_) => write!(f, "#<port>"),
+            Value::PARAMETER(_) => write!(f, "#<parameter>"),
+            Value::ENVIRONMENT(_) => write!(f, "#<environment>"),
+            Value::EOF => write!(f, "#<eof>"),
+        }
+    }
+}
+
+// Print the pairs, vectors and records of `value` with `atom` printing what
+// they contain. One reached again while it is being printed is labelled `#n=`
+// where it is first printed and written `#n#` after that, so cycles end; with
+// `shared` so is one reached more than once at all.
+pub fn print(value: &Value, shared: bool, atom: &dyn Fn(&Value) -> String) -> String {
+    let mut labels = HashSet::new();
+    find_labels(value, shared, &mut HashSet::new(), &mut HashSet::new(), &mut labels);
+    let mut printer = Printer {
+        labels: labels.into_iter().map(|object| (object, None)).collect(),
+        count: 0,
+        atom,
+    };
+    let mut text = String::new();
+    printer.print(value, &mut text);
+    text
+}
+
+fn is_compound(value: &Value) -> bool {
+    matches!(value, Value::PAIR { .. } | Value::VECTOR(_) | Value::RECORD { .. })
+}
+
+// Walk the objects in the order they are printed, `open` are those being
+// printed at that point
+fn find_labels(
+    value: &Value,
+    shared: bool,
+    seen: &mut HashSet<*const Value>,
+    open: &mut HashSet<*const Value>,
+    labels: &mut HashSet<*const Value>,
+) {
+    if let Value::VALUES(values) = value {
+        for value in values {
+            find_labels(value, shared, seen, open, labels);
+        }
+        return;
+    }
+    // the pairs along a list are open until its end is printed
+    let mut opened = Vec::new();
+    let mut value = value;
+    while is_compound(value) {
+        let object = value as *const Value;
+        if !seen.insert(object) {
+            if shared || open.contains(&object) {
+                labels.insert(object);
+            }
+            break;
+        }
+        open.insert(object);
+        opened.push(object);
+        match value {
+            Value::PAIR { car, cdr } => {
+                find_labels(car, shared, seen, open, labels);
+                value = cdr;
+            }
+            Value::VECTOR(values) => {
+                for value in values.borrow().iter() {
+                    find_labels(value, shared, seen, open, labels);
+                }
+                break;
+            }
+            Value::RECORD { fields, .. } => {
+                for value in fields.borrow().iter() {
+                    find_labels(value, shared, seen, open, labels);
+                }
+                break;
+            }
+            _ => unreachable!(),
+        }
+    }
+    for object in opened {
+        open.remove(&object);
+    }
+}
+
+struct Printer<'a> {
+    // the objects to label, with their number once printed
+    labels: HashMap<*const Value, Option<usize>>,
+    count: usize,
+    atom: &'a dyn Fn(&Value) -> String,
+}
+
+impl Printer<'_> {
+    fn print(&mut self, value: &Value, text: &mut String) {
+        if let Some(label) = self.labels.get_mut(&(value as *const Value)) {
+            match label {
+                Some(n) => {
+                    text.push_str(&format!("#{}#", n));
+                    return;
+                }
+                None => {
+                    *label = Some(self.count);
+                    text.push_str(&format!("#{}=", self.count));
+                    self.count += 1;
+                }
+            }
+        }
+        match value {
+            Value::PAIR { car, cdr } => {
+                text.push('(');
+                self.print(car, text);
+                let mut rest: &Value = cdr;
+                loop {
+                    match rest {
+                        Value::PAIR { car, cdr } if !self.labels.contains_key(&(rest as *const Value)) => {
+                            text.push(' ');
+                            self.print(car, text);
+                            rest = cdr;
+                        }
+                        _ if rest.is_nil() => break,
+                        _ => {
+                            text.push_str(" . ");
+                            self.print(rest, text);
+                            break;
+                        }
+                    }
+                }
+                text.push(')');
+            }
+            Value::VECTOR(values) => {
+                text.push_str("#(");
+                for (i, value) in values.borrow().iter().enumerate() {
+                    if i > 0 {
+                        text.push(' ');
+                    }
+                    self.print(value, text);
+                }
+                text.push(')');
+            }
+            Value::RECORD { rtd, fields } => {
+                text.push_str(&format!("#<{}", rtd.name));
+                for (name, value) in rtd.fields.iter().zip(fields.borrow().iter()) {
+                    text.push_str(&format!(" {}: ", name));
+                    self.print(value, text);
+                }
+                text.push('>');
+            }
+            Value::VALUES(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        text.push(' ');
+                    }
+                    self.print(value, text);
+                }
+            }
+            _ => text.push_str(&(self.atom)(value)),
         }
     }
 }
 
+impl Value {
+    pub fn nil() -> Rc<Value> {
+        Rc::new(Value::PRIMITIVE(Rc::new(Primitive::NIL)))
+    }
+
+    // Everything but #f counts as true in a conditional
+    pub fn is_true(&self) -> bool {
+        !matches!(self, Value::PRIMITIVE(primitive) if matches!(**primitive, Primitive::BOOLEAN(false)))
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::PRIMITIVE(primitive) if matches!(**primitive, Primitive::NIL))
+    }
+}
+
+impl Clojure {
+    pub fn formals(&self) -> &Formals {
+        &self.proto.formals
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Env {
+    // The builtins and the procedures of the prelude, in a layer of its own
+    // over those shared by every environment under the current policy
     pub fn get_initialized_env() -> Rc<RefCell<Env>> {
+        Self::layer(&prelude_env())
+    }
+    fn build_prelude_env() -> Rc<RefCell<Env>> {
+        let env = Self::get_builtin_env();
+        for top in parse_pest(PRELUDE).tops {
+            eval(top, &env).expect("the prelude evaluates");
+        }
+        // prelude procedures the policy denies are dropped as well
+        let policy = policy::current();
+        env.borrow_mut().values.retain(|name, _| policy.permits(name));
+        env
+    }
+    // The builtins alone, for a smaller environment; only those the current
+    // policy permits
+    pub fn get_builtin_env() -> Rc<RefCell<Env>> {
+        let policy = policy::current();
         let env = Rc::new(RefCell::new(Env::new()));
         for (name, func) in get_builtins() {
-            env.borrow_mut()
-                .insert(name.to_string(), Rc::new(Value::BUILTIN(func)));
+            if policy.permits(name) {
+                env.borrow_mut()
+                    .insert(name.to_string(), Rc::new(Value::BUILTIN(func)));
+            }
+        }
+        for (name, value) in get_constants() {
+            if policy.permits(name) {
+                env.borrow_mut().insert(name.to_string(), value);
+            }
+        }
+        if policy.permits("interaction-environment") {
+            Self::bind_interaction(&env);
         }
         env
     }
+    // A parameter, so calling it gives the environment being set up
+    fn bind_interaction(env: &Rc<RefCell<Env>>) {
+        let interaction = Rc::new(Value::PARAMETER(Rc::new(Parameter {
+            value: RefCell::new(Rc::new(Value::ENVIRONMENT(env.clone()))),
+            converter: None,
+        })));
+        env.borrow_mut()
+            .insert("interaction-environment".to_string(), interaction);
+    }
+    // An environment of its own on top of `base`, which it doesn't change:
+    // definitions land in the layer and names sealed in `base` stay sealed
+    pub fn layer(base: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
+        let mut layer = Env::with_parent(base.clone());
+        layer.sealed = base.borrow().sealed.clone();
+        let has_interaction = base.borrow().values.contains_key("interaction-environment");
+        let layer = Rc::new(RefCell::new(layer));
+        if has_interaction {
+            Self::bind_interaction(&layer);
+        }
+        layer
+    }
     pub fn new() -> Self {
         Self {
             parent: None,
             values: HashMap::new(),
+            sealed: HashSet::new(),
         }
     }
     pub fn with_parent(parent: Rc<RefCell<Env>>) -> Self {
         Self {
             parent: Some(parent),
             values: HashMap::new(),
+            sealed: HashSet::new(),
         }
     }
-    fn resolve(&self, identifier: &str) -> Option<Rc<Value>> {
+    pub fn resolve(&self, identifier: &str) -> Option<Rc<Value>> {
         self.values
             .get(identifier)
             .map(|value| (*value).clone())
             .or_else(|| {
                 self.parent
                     .as_ref()
-                    .and_then(|parent| (parent.borrow().resolve(identifier)))
+                    .and_then(|parent| parent.borrow().resolve(identifier))
             })
     }
 
-    fn insert(&mut self, identifier: String, value: Rc<Value>) {
+    pub fn insert(&mut self, identifier: String, value: Rc<Value>) {
         self.values.insert(identifier, value);
     }
+    // Definitions may add names but not replace those visible so far
+    pub fn seal(&mut self) {
+        self.sealed.extend(self.values.keys().cloned());
+        let mut parent = self.parent.clone();
+        while let Some(env) = parent {
+            self.sealed.extend(env.borrow().values.keys().cloned());
+            parent = env.borrow().parent.clone();
+        }
+    }
+    // Whether defining the name here would replace a sealed binding
+    pub fn is_sealed_binding(&self, identifier: &str) -> bool {
+        self.sealed.contains(identifier)
+    }
+}
+
+// an environment and its copy
+type EnvCopy = (Rc<RefCell<Env>>, Rc<RefCell<Env>>);
+
+// Copies values and the environments they refer to, keeping what is shared
+// in the original shared in the copy. The prelude environments are immutable
+// and stay shared with the original, as do ports and the current port
+// parameters, which the instance running installs its ports in.
+pub struct Copier {
+    values: HashMap<*const Value, Rc<Value>>,
+    envs: HashMap<*const RefCell<Env>, Rc<RefCell<Env>>>,
+    promises: HashMap<*const RefCell<Promise>, Rc<RefCell<Promise>>>,
+    // environments made but not filled yet, filled by the outermost copy so
+    // that cycles through them end at the empty copy
+    pending: Vec<EnvCopy>,
+}
+
+impl Default for Copier {
+    fn default() -> Self {
+        let values = get_constants()
+            .into_iter()
+            .map(|(_, value)| (Rc::as_ptr(&value), value))
+            .collect();
+        Self {
+            values,
+            envs: HashMap::new(),
+            promises: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Copier {
+    pub fn env(&mut self, env: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
+        let copy = self.env_shell(env);
+        self.fill();
+        copy
+    }
+    pub fn value(&mut self, value: &Rc<Value>) -> Rc<Value> {
+        let copy = self.copy(value);
+        self.fill();
+        copy
+    }
+    fn env_shell(&mut self, env: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
+        if is_prelude_env(env) {
+            return env.clone();
+        }
+        if let Some(copy) = self.envs.get(&Rc::as_ptr(env)) {
+            return copy.clone();
+        }
+        let copy = Rc::new(RefCell::new(Env::new()));
+        self.envs.insert(Rc::as_ptr(env), copy.clone());
+        self.pending.push((env.clone(), copy.clone()));
+        copy
+    }
+    fn fill(&mut self) {
+        while let Some((env, copy)) = self.pending.pop() {
+            let env = env.borrow();
+            let parent = env.parent.as_ref().map(|parent| self.env_shell(parent));
+            let values = env
+                .values
+                .iter()
+                .map(|(name, value)| (name.clone(), self.copy(value)))
+                .collect();
+            *copy.borrow_mut() = Env {
+                parent,
+                values,
+                sealed: env.sealed.clone(),
+            };
+        }
+    }
+    fn closure(&mut self, clojure: &Rc<Clojure>) -> Rc<Clojure> {
+        let env = self.env_shell(&clojure.env);
+        if Rc::ptr_eq(&env, &clojure.env) {
+            return clojure.clone();
+        }
+        Rc::new(Clojure {
+            proto: clojure.proto.clone(),
+            env,
+        })
+    }
+    fn copy(&mut self, value: &Rc<Value>) -> Rc<Value> {
+        if let Some(copy) = self.values.get(&Rc::as_ptr(value)) {
+            return copy.clone();
+        }
+        // a list is copied from its end, without recursing along it
+        if let Value::PAIR { .. } = &**value {
+            let mut spine = Vec::new();
+            let mut rest = value.clone();
+            while let Value::PAIR { car, cdr } = &*rest {
+                if self.values.contains_key(&Rc::as_ptr(&rest)) {
+                    break;
+                }
+                spine.push((rest.clone(), car.clone()));
+                let cdr = cdr.clone();
+                rest = cdr;
+            }
+            let mut tail = self.copy(&rest);
+            for (pair, car) in spine.into_iter().rev() {
+                let car = self.copy(&car);
+                tail = Rc::new(Value::PAIR { car, cdr: tail });
+                self.values.insert(Rc::as_ptr(&pair), tail.clone());
+            }
+            return tail;
+        }
+        // containers are registered before their contents are copied, so
+        // they may contain themselves
+        let copy = match &**value {
+            Value::PRIMITIVE(primitive) => match &**primitive {
+                Primitive::STRING(string) => Rc::new(Value::PRIMITIVE(Rc::new(Primitive::STRING(
+                    RefCell::new(string.borrow().clone()),
+                )))),
+                _ => value.clone(),
+            },
+            Value::CLOSURE(clojure) => {
+                let copy = self.closure(clojure);
+                if Rc::ptr_eq(&copy, clojure) {
+                    value.clone()
+                } else {
+                    Rc::new(Value::CLOSURE(copy))
+                }
+            }
+            Value::CASELAMBDA(clauses) => {
+                let copies: Vec<_> = clauses.iter().map(|clause| self.closure(clause)).collect();
+                if copies.iter().zip(clauses).all(|(copy, clause)| Rc::ptr_eq(copy, clause)) {
+                    value.clone()
+                } else {
+                    Rc::new(Value::CASELAMBDA(copies))
+                }
+            }
+            Value::VECTOR(values) => {
+                let copy = Rc::new(Value::VECTOR(RefCell::new(Vec::new())));
+                self.values.insert(Rc::as_ptr(value), copy.clone());
+                let values: Vec<_> = values.borrow().iter().map(|value| self.copy(value)).collect();
+                if let Value::VECTOR(copied) = &*copy {
+                    *copied.borrow_mut() = values;
+                }
+                copy
+            }
+            Value::BYTEVECTOR(bytes) => Rc::new(Value::BYTEVECTOR(RefCell::new(bytes.borrow().clone()))),
+            Value::RECORD { rtd, fields } => {
+                let copy = Rc::new(Value::RECORD {
+                    rtd: rtd.clone(),
+                    fields: RefCell::new(Vec::new()),
+                });
+                self.values.insert(Rc::as_ptr(value), copy.clone());
+                let values: Vec<_> = fields.borrow().iter().map(|value| self.copy(value)).collect();
+                if let Value::RECORD { fields, .. } = &*copy {
+                    *fields.borrow_mut() = values;
+                }
+                copy
+            }
+            Value::PROMISE(promise) => {
+                let state = promise.borrow().clone();
+                let copy = match self.promises.get(&Rc::as_ptr(&state)) {
+                    Some(copy) => copy.clone(),
+                    None => {
+                        let copy = Rc::new(RefCell::new(Promise::DONE(Rc::new(Value::UNSPECIFIED))));
+                        self.promises.insert(Rc::as_ptr(&state), copy.clone());
+                        let copied = match &*state.borrow() {
+                            Promise::DONE(value) => Promise::DONE(self.copy(value)),
+                            Promise::DELAYED { thunk, lazy } => Promise::DELAYED {
+                                thunk: self.copy(thunk),
+                                lazy: *lazy,
+                            },
+                        };
+                        *copy.borrow_mut() = copied;
+                        copy
+                    }
+                };
+                Rc::new(Value::PROMISE(RefCell::new(copy)))
+            }
+            Value::HASHTABLE(table) => {
+                let equivalence = table.borrow().equivalence;
+                let copy = Rc::new(Value::HASHTABLE(RefCell::new(HashTable {
+                    equivalence,
+                    buckets: HashMap::new(),
+                })));
+                self.values.insert(Rc::as_ptr(value), copy.clone());
+                // keys hashed by identity hash differently once copied
+                for (key, entry) in table.borrow().entries() {
+                    let (key, entry) = (self.copy(&key), self.copy(&entry));
+                    if let Value::HASHTABLE(copied) = &*copy {
+                        copied.borrow_mut().set(key, entry);
+                    }
+                }
+                copy
+            }
+            Value::PARAMETER(parameter) => {
+                let converter = parameter.converter.as_ref().map(|converter| self.copy(converter));
+                let current = parameter.value.borrow().clone();
+                let copy = Rc::new(Parameter {
+                    value: RefCell::new(Rc::new(Value::UNSPECIFIED)),
+                    converter,
+                });
+                let copy = Rc::new(Value::PARAMETER(copy));
+                self.values.insert(Rc::as_ptr(value), copy.clone());
+                let current = self.copy(&current);
+                if let Value::PARAMETER(copied) = &*copy {
+                    copied.value.replace(current);
+                }
+                copy
+            }
+            Value::ENVIRONMENT(env) => Rc::new(Value::ENVIRONMENT(self.env_shell(env))),
+            Value::VALUES(values) => Rc::new(Value::VALUES(values.iter().map(|value| self.copy(value)).collect())),
+            Value::BUILTIN(_)
+            | Value::UNSPECIFIED
+            | Value::RECORDTYPE(_)
+            | Value::PORT(_)
+            | Value::EOF
+            | Value::PAIR { .. } => value.clone(),
+        };
+        self.values.insert(Rc::as_ptr(value), copy.clone());
+        copy
+    }
+}
+
+// The prelude evaluated once for each policy in use, procedures defined there
+// see the builtins whatever the environments on top define
+type PreludeEnvs = Vec<(Weak<policy::Policy>, Rc<RefCell<Env>>)>;
+
+thread_local! {
+    static PRELUDE_ENVS: RefCell<PreludeEnvs> = const { RefCell::new(Vec::new()) };
+    // The environment of the top-level form being evaluated
+    static TOP_LEVEL: RefCell<Option<Rc<RefCell<Env>>>> = const { RefCell::new(None) };
+}
+
+fn is_prelude_env(env: &Rc<RefCell<Env>>) -> bool {
+    PRELUDE_ENVS.with(|envs| envs.borrow().iter().any(|(_, prelude)| Rc::ptr_eq(prelude, env)))
+}
+
+fn prelude_env() -> Rc<RefCell<Env>> {
+    let policy = policy::current();
+    let cached = PRELUDE_ENVS.with(|envs| {
+        let mut envs = envs.borrow_mut();
+        envs.retain(|(policy, _)| policy.strong_count() > 0);
+        envs.iter()
+            .find(|(cached, _)| std::ptr::eq(cached.as_ptr(), Rc::as_ptr(&policy)))
+            .map(|(_, env)| env.clone())
+    });
+    cached.unwrap_or_else(|| {
+        let env = Env::build_prelude_env();
+        PRELUDE_ENVS.with(|envs| envs.borrow_mut().push((Rc::downgrade(&policy), env.clone())));
+        env
+    })
+}
+
+// The environment `load` defaults to
+pub fn top_level() -> Option<Rc<RefCell<Env>>> {
+    TOP_LEVEL.with(|top_level| top_level.borrow().clone())
 }
 
 #[allow(unused)]
 pub fn eval(top: Top, env: &Rc<RefCell<Env>>) -> Result<Option<Rc<Value>>, String> {
+    struct Restore(Option<Rc<RefCell<Env>>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            TOP_LEVEL.with(|top_level| top_level.replace(self.0.take()));
+        }
+    }
+    let _restore = Restore(TOP_LEVEL.with(|top_level| top_level.replace(Some(env.clone()))));
     let res = match top {
-        Top::DEC {
-            identifier,
-            expression,
-        } => {
+        Top::DEC { target, expression } => {
             let value = eval_expr(expression, env).ok_or("error")?;
-            env.borrow_mut().insert(identifier, value);
+            define(env, &target, value).ok_or("error")?;
             None
         }
         Top::EXP { expression } => eval_expr(expression, env),
+        Top::IMPORT(sets) => {
+            library::import(env, &sets).ok_or("error")?;
+            None
+        }
+        Top::LIBRARY(library) => {
+            library::define_library(library).ok_or("error")?;
+            None
+        }
     };
     Ok(res)
 }
 
+// Calls and conditionals in tail position continue the loop instead of
+// recursing, so iteration written as recursion runs in constant stack space
 fn eval_expr(expr: Rc<Exp>, env: &Rc<RefCell<Env>>) -> Option<Rc<Value>> {
-    // println!("eval_expr: {:?}", expr);
-    match &*expr {
-        Exp::IDENTIFIER(identifier) => env.borrow().resolve(&identifier),
-        Exp::LITERIAL(datum) => eval_datum(datum.clone(), env),
-        Exp::CALL { operator, operands } => {
-            let operator = eval_expr(operator.clone(), env)?;
-            let operands = operands
-                .into_iter()
-                .map(|operand| eval_expr(operand.clone(), env))
-                .collect::<Option<Vec<_>>>()?;
-            apply(operator, operands, env)
-        }
-        Exp::COND {
-            test,
-            consequent,
-            alternative,
-        } => {
-            let test = eval_expr(test.clone(), env)?;
-            if let Value::PRIMITIVE(datum) = &*test {
-                if let Primitive::BOOLEAN(true) = &**datum {
-                    return eval_expr(consequent.clone(), env);
-                } else {
-                    if let Some(alternative) = alternative {
-                        return eval_expr(alternative.clone(), env);
-                    }
+    let _depth = limits::enter();
+    let mut expr = expr;
+    let mut env = env.clone();
+    loop {
+        limits::step();
+        // println!("eval_expr: {:?}", expr);
+        let current = expr.clone();
+        match &*current {
+            Exp::IDENTIFIER(identifier) => return env.borrow().resolve(identifier),
+            Exp::LITERIAL(datum) => return eval_datum(datum.clone(), &env),
+            Exp::CALL { operator, operands } => {
+                let operator = eval_expr(operator.clone(), &env)?;
+                let operands = operands
+                    .iter()
+                    .map(|operand| eval_expr(operand.clone(), &env))
+                    .collect::<Option<Vec<_>>>()?;
+                let clojure = match &*operator {
+                    Value::CLOSURE(clojure) => clojure.clone(),
+                    Value::CASELAMBDA(clauses) => dispatch(clauses, operands.len())?,
+                    _ => return apply(operator, operands),
+                };
+                env = bind(&clojure, operands)?;
+                let (last, init) = clojure.proto.body.split_last()?;
+                for expression in init {
+                    eval_expr(expression.clone(), &env)?;
                 }
+                expr = last.clone();
+            }
+            Exp::COND {
+                test,
+                consequent,
+                alternative,
+            } => {
+                let test = eval_expr(test.clone(), &env)?;
+                expr = match (test.is_true(), alternative) {
+                    (true, _) => consequent.clone(),
+                    (false, Some(alternative)) => alternative.clone(),
+                    (false, None) => return Some(Rc::new(Value::UNSPECIFIED)),
+                };
             }
-            None
         }
     }
 }
 
 fn eval_datum(datum: Rc<Datum>, env: &Rc<RefCell<Env>>) -> Option<Rc<Value>> {
+    limits::charge(limits::size_of_values(1));
     match &*datum {
         Datum::LAMBDA(lambda) => Some(Rc::new(Value::CLOSURE(Rc::new(Clojure {
             proto: lambda.clone(),
             env: env.clone(),
         })))),
-        Datum::PRIMITIVE(primitive) => Some(Rc::new(Value::PRIMITIVE(primitive.clone()))),
+        Datum::CASELAMBDA(clauses) => {
+            let clauses = clauses
+                .iter()
+                .map(|lambda| {
+                    Rc::new(Clojure {
+                        proto: lambda.clone(),
+                        env: env.clone(),
+                    })
+                })
+                .collect();
+            Some(Rc::new(Value::CASELAMBDA(clauses)))
+        }
+        // a string of its own each time, like vectors, so mutating it leaves the literal alone
+        Datum::PRIMITIVE(primitive) => match &**primitive {
+            Primitive::STRING(string) => {
+                let string = string.borrow().clone();
+                limits::charge(string.len());
+                Some(Rc::new(Value::PRIMITIVE(Rc::new(Primitive::STRING(RefCell::new(string))))))
+            }
+            _ => Some(Rc::new(Value::PRIMITIVE(primitive.clone()))),
+        },
         Datum::PAIR((car, cdr)) => {
-            let car = car.as_ref().and_then(|car| eval_datum(car.clone(), env));
-            let cdr = cdr.as_ref().and_then(|cdr| eval_datum(cdr.clone(), env));
+            let car = eval_datum(car.clone(), env)?;
+            let cdr = eval_datum(cdr.clone(), env)?;
             Some(Rc::new(Value::PAIR { car, cdr }))
         }
+        Datum::VECTOR(data) => {
+            let values = data
+                .iter()
+                .map(|datum| eval_datum(datum.clone(), env))
+                .collect::<Option<Vec<_>>>()?;
+            Some(Rc::new(Value::VECTOR(RefCell::new(values))))
+        }
+        Datum::BYTEVECTOR(bytes) => Some(Rc::new(Value::BYTEVECTOR(RefCell::new(bytes.clone())))),
     }
 }
 
-#[allow(unused)]
-fn apply(
-    operator: Rc<Value>,
-    operands: Vec<Rc<Value>>,
-    env: &Rc<RefCell<Env>>,
-) -> Option<Rc<Value>> {
+// The value a quotation of the datum evaluates to
+pub fn quote(datum: Rc<Datum>) -> Option<Rc<Value>> {
+    eval_datum(datum, &Rc::new(RefCell::new(Env::new())))
+}
+
+pub fn apply(operator: Rc<Value>, operands: Vec<Rc<Value>>) -> Option<Rc<Value>> {
     match &*operator {
         Value::BUILTIN(builtin) => builtin(operands),
-        Value::CLOSURE(clojure) => {
-            let Lambda {
-                ifvarlen,
-                parameters,
-                definitions,
-                body,
-            } = &*clojure.proto;
-            let env = &clojure.env;
-            // should use lexical scope
-            let mut env = Rc::new(RefCell::new(Env::with_parent(env.clone())));
-            if !ifvarlen {
-                if parameters.len() != operands.len() {
-                    println!("wrong number of arguments!");
-                    return None;
-                }
-                for (parameter, operand) in parameters.iter().zip(operands) {
-                    env.borrow_mut().insert(parameter.clone(), operand);
-                }
-            } else {
-                let oprand = operands.into_iter().rev().fold(
-                    Rc::new(Value::PRIMITIVE(Rc::new(Primitive::NIL))),
-                    |acc, oprand| {
-                        Rc::new(Value::PAIR {
-                            car: Some(oprand),
-                            cdr: Some(acc),
-                        })
-                    },
-                );
-                env.borrow_mut().insert(parameters[0].clone(), oprand);
-            }
-            for (identifier, expression) in definitions {
-                let value = eval_expr(expression.clone(), &env)?;
-                env.borrow_mut().insert(identifier.clone(), value);
-            }
-            eval_expr(body.clone(), &env)
+        Value::CLOSURE(clojure) => call(clojure, operands),
+        Value::CASELAMBDA(clauses) => call(&*dispatch(clauses, operands.len())?, operands),
+        Value::PARAMETER(parameter) => {
+            if !operands.is_empty() {
+                println!("wrong number of arguments: expected 0, got {}!", operands.len());
+                return None;
+            }
+            Some(parameter.value.borrow().clone())
         }
         _ => {
             println!("call on non-function!");
@@ -221,3 +861,120 @@ fn apply(
         }
     }
 }
+
+fn call(clojure: &Clojure, operands: Vec<Rc<Value>>) -> Option<Rc<Value>> {
+    let env = bind(clojure, operands)?;
+    let mut res = None;
+    for expression in &clojure.proto.body {
+        res = Some(eval_expr(expression.clone(), &env)?);
+    }
+    res
+}
+
+// The first clause of a case-lambda accepting `count` arguments
+fn dispatch(clauses: &[Rc<Clojure>], count: usize) -> Option<Rc<Clojure>> {
+    let clause = clauses
+        .iter()
+        .find(|clause| clause.proto.formals.accepts(count));
+    if clause.is_none() {
+        println!("wrong number of arguments: no clause accepts {}!", count);
+    }
+    clause.cloned()
+}
+
+impl Formals {
+    // The minimum number of arguments, and the maximum unless there is a rest parameter
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        let required = self.parameters.len();
+        match self.rest {
+            Some(_) => (required, None),
+            None => (required, Some(required)),
+        }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        match self.arity() {
+            (min, None) => count >= min,
+            (min, Some(max)) => min <= count && count <= max,
+        }
+    }
+}
+
+// Create the environment of a closure call: bind the parameters, then
+// evaluate the internal definitions of the body in order
+fn bind(clojure: &Clojure, operands: Vec<Rc<Value>>) -> Option<Rc<RefCell<Env>>> {
+    let Lambda {
+        formals,
+        definitions,
+        body: _,
+    } = &*clojure.proto;
+    // should use lexical scope
+    let env = Rc::new(RefCell::new(Env::with_parent(clojure.env.clone())));
+    bind_formals(&env, formals, operands)?;
+    for (target, expression) in definitions {
+        let value = eval_expr(expression.clone(), &env)?;
+        define(&env, target, value)?;
+    }
+    Some(env)
+}
+
+fn bind_formals(env: &Rc<RefCell<Env>>, formals: &Formals, values: Vec<Rc<Value>>) -> Option<()> {
+    let Formals { parameters, rest } = formals;
+    if !formals.accepts(values.len()) {
+        match rest {
+            Some(_) => println!(
+                "wrong number of arguments: expected at least {}, got {}!",
+                parameters.len(),
+                values.len()
+            ),
+            None => println!(
+                "wrong number of arguments: expected {}, got {}!",
+                parameters.len(),
+                values.len()
+            ),
+        }
+        return None;
+    }
+    let mut values = values.into_iter();
+    for parameter in parameters {
+        env.borrow_mut().insert(parameter.clone(), values.next()?);
+    }
+    if let Some(rest) = rest {
+        let list = values.rev().fold(Value::nil(), |acc, value| {
+            Rc::new(Value::PAIR {
+                car: value,
+                cdr: acc,
+            })
+        });
+        env.borrow_mut().insert(rest.clone(), list);
+    }
+    Some(())
+}
+
+fn check_sealed(env: &Rc<RefCell<Env>>, identifier: &str) -> Option<()> {
+    if env.borrow().is_sealed_binding(identifier) {
+        println!("cannot redefine sealed binding {}!", identifier);
+        return None;
+    }
+    Some(())
+}
+
+fn define(env: &Rc<RefCell<Env>>, target: &Target, value: Rc<Value>) -> Option<()> {
+    match target {
+        Target::IDENTIFIER(identifier) => {
+            check_sealed(env, identifier)?;
+            env.borrow_mut().insert(identifier.clone(), value);
+            Some(())
+        }
+        Target::FORMALS(formals) => {
+            for identifier in formals.parameters.iter().chain(&formals.rest) {
+                check_sealed(env, identifier)?;
+            }
+            let values = match &*value {
+                Value::VALUES(values) => values.clone(),
+                _ => vec![value.clone()],
+            };
+            bind_formals(env, formals, values)
+        }
+    }
+}
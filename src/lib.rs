@@ -4,31 +4,287 @@ extern crate pest_derive;
 
 pub mod builtin;
 pub mod interpreter;
+pub mod library;
+pub mod limits;
 pub mod parser;
+pub mod policy;
+pub mod worker;
 
+use builtin::Ports;
 use interpreter::*;
+use library::Libraries;
+use limits::{Budget, Interrupt, LimitExceeded, Limits, Usage};
 use parser::*;
+use policy::Policy;
 use std::cell::RefCell;
+use std::io::{Read, Write};
 use std::rc::Rc;
 
 // Scheme-Rust Interface
 
+// Not `Send`, values and environments are shared through `Rc`; to use one
+// from other threads keep it on a `worker::Worker`
 pub struct Katsu {
     env: Rc<RefCell<Env>>,
+    // the current ports while this interpreter evaluates, the console by default
+    ports: Ports,
+    budget: RefCell<Budget>,
+    interrupt: Interrupt,
+    policy: Rc<Policy>,
+    libraries: Rc<Libraries>,
+}
+
+// A sink that keeps what is written to it, for reading back from the host
+#[derive(Clone, Default)]
+pub struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Capture {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+    // The contents written since the last take
+    pub fn take(&self) -> String {
+        String::from_utf8_lossy(&self.0.take()).into_owned()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// A sink passing each write on to a callback as text
+struct Callback<F: FnMut(&str)>(F);
+
+impl<F: FnMut(&str)> Write for Callback<F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        (self.0)(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn output_port(sink: impl Write + 'static) -> Rc<Value> {
+    Rc::new(Value::PORT(Port::output(Output::WRITER(Box::new(sink)), true)))
+}
+
+// A copy of the state of an interpreter for new instances to start from.
+// Each instance gets copies of its own of what was defined, objects mutated
+// by one are left alone in the others; only the prelude and ports are shared.
+// Like `Katsu` it stays on the thread that made it, and each instance is a
+// deep copy of everything defined, taking time in proportion to it.
+pub struct Snapshot {
+    env: Rc<RefCell<Env>>,
+    limits: Limits,
+    policy: Rc<Policy>,
+    libraries: Rc<Libraries>,
+}
+
+impl Snapshot {
+    // A fresh interpreter with the limits, policy and libraries of the one
+    // snapshotted, without its ports or usage
+    pub fn instantiate(&self) -> Katsu {
+        let mut copier = Copier::default();
+        Katsu {
+            env: copier.env(&self.env),
+            ports: Ports::default(),
+            budget: RefCell::new(Budget {
+                limits: self.limits,
+                usage: Usage::default(),
+            }),
+            interrupt: Interrupt::default(),
+            policy: self.policy.clone(),
+            libraries: Rc::new(self.libraries.copy(&mut copier)),
+        }
+    }
+}
+
+impl Default for Katsu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Options fixed when the interpreter is created
+#[derive(Clone)]
+pub struct KatsuBuilder {
+    prelude: bool,
+    limits: Limits,
+    policy: Policy,
+    library_path: Vec<std::path::PathBuf>,
+}
+
+impl KatsuBuilder {
+    // Whether the procedures of the prelude are defined, a sandbox may do with
+    // the builtins alone
+    pub fn prelude(mut self, prelude: bool) -> Self {
+        self.prelude = prelude;
+        self
+    }
+    // Unlimited unless given
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+    // Everything is permitted unless given
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+    // Where imports look for `.sld` files, after the current directory
+    pub fn library_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.library_path.push(path.into());
+        self
+    }
+    pub fn build(self) -> Katsu {
+        let libraries = Libraries::default();
+        for path in self.library_path {
+            libraries.add_search_path(path);
+        }
+        let policy = Rc::new(self.policy);
+        let env = policy::install(&policy, || {
+            if self.prelude {
+                Env::get_initialized_env()
+            } else {
+                Env::get_builtin_env()
+            }
+        });
+        if policy.is_sealed() {
+            env.borrow_mut().seal();
+        }
+        Katsu {
+            env,
+            ports: Ports::default(),
+            budget: RefCell::new(Budget {
+                limits: self.limits,
+                usage: Usage::default(),
+            }),
+            interrupt: Interrupt::default(),
+            policy,
+            libraries: Rc::new(libraries),
+        }
+    }
 }
 
 impl Katsu {
     pub fn new() -> Self {
-        let env = Env::get_initialized_env();
-        Self { env }
+        Self::builder().build()
     }
-    pub fn eval(&self, program: &str) -> Vec<Option<Rc<Value>>> {
-        let ast = parse(&program);
-        let mut res = Vec::new();
-        for top in ast.tops {
-            res.push(interpreter::eval(top, &self.env).unwrap());
+    pub fn builder() -> KatsuBuilder {
+        KatsuBuilder {
+            prelude: true,
+            limits: Limits::default(),
+            policy: Policy::default(),
+            library_path: Vec::new(),
         }
-        res
+    }
+    // What scripts write to the current output port goes to the sink
+    pub fn set_output(&mut self, sink: impl Write + 'static) {
+        self.ports.output = Some(output_port(sink));
+    }
+    pub fn set_error(&mut self, sink: impl Write + 'static) {
+        self.ports.error = Some(output_port(sink));
+    }
+    pub fn set_output_callback(&mut self, callback: impl FnMut(&str) + 'static) {
+        self.set_output(Callback(callback));
+    }
+    pub fn set_error_callback(&mut self, callback: impl FnMut(&str) + 'static) {
+        self.set_error(Callback(callback));
+    }
+    // Scripts read the current input port from the source
+    pub fn set_input(&mut self, source: impl Read + 'static) {
+        self.ports.input = Some(Rc::new(Value::PORT(Port::input(
+            Box::new(source),
+            true,
+            false,
+        ))));
+    }
+    // Collect the output of scripts instead of printing it
+    pub fn capture_output(&mut self) -> Capture {
+        let capture = Capture::default();
+        self.set_output(capture.clone());
+        capture
+    }
+    pub fn capture_error(&mut self) -> Capture {
+        let capture = Capture::default();
+        self.set_error(capture.clone());
+        capture
+    }
+    // Where imports of this interpreter look for `.sld` files, after those added before
+    pub fn add_library_path(&self, path: impl Into<std::path::PathBuf>) {
+        self.libraries.add_search_path(path);
+    }
+    pub fn limits(&self) -> Limits {
+        self.budget.borrow().limits
+    }
+    // Raising a limit that was hit lets evaluation go on from where the
+    // environment was left
+    pub fn set_limits(&self, limits: Limits) {
+        self.budget.borrow_mut().limits = limits;
+    }
+    // What has been used against the limits since creation or the last reset
+    pub fn usage(&self) -> Usage {
+        self.budget.borrow().usage
+    }
+    pub fn reset_usage(&self) {
+        self.budget.borrow_mut().usage = Usage::default();
+    }
+    // What has been defined so far, copied for new instances to start from;
+    // this interpreter goes on unaffected by them and they by it
+    pub fn snapshot(&self) -> Snapshot {
+        let mut copier = Copier::default();
+        Snapshot {
+            env: copier.env(&self.env),
+            limits: self.limits(),
+            policy: self.policy.clone(),
+            libraries: Rc::new(self.libraries.copy(&mut copier)),
+        }
+    }
+    // Another thread triggers it to stop the evaluation running then, or the
+    // next one when none is, which fails with `Limit::INTERRUPT`
+    pub fn interrupt_handle(&self) -> Interrupt {
+        self.interrupt.clone()
+    }
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+    // Run `f` with the ports, budget, policy and libraries of this
+    // interpreter, a limit being hit unwinds to here
+    fn run<T>(&self, f: impl FnOnce() -> T) -> Result<T, LimitExceeded> {
+        let run = std::panic::AssertUnwindSafe(|| {
+            policy::install(&self.policy, || {
+                library::install(&self.libraries, || {
+                    limits::install(&self.budget, &self.interrupt, || self.ports.install(f))
+                })
+            })
+        });
+        std::panic::catch_unwind(run).map_err(|payload| match limits::as_exceeded(&*payload) {
+            Some(exceeded) => exceeded,
+            None => std::panic::resume_unwind(payload),
+        })
+    }
+    // The definitions evaluated before a limit is hit stay in the environment
+    pub fn try_eval(&self, program: &str) -> Result<Vec<Option<Rc<Value>>>, LimitExceeded> {
+        self.run(|| {
+            // includes are read while parsing, under the policy
+            let ast = parse(program);
+            let mut res = Vec::new();
+            for top in ast.tops {
+                res.push(interpreter::eval(top, &self.env).unwrap());
+            }
+            res
+        })
+    }
+    pub fn eval(&self, program: &str) -> Vec<Option<Rc<Value>>> {
+        self.try_eval(program)
+            .unwrap_or_else(|exceeded| panic!("limit exceeded: {}", exceeded))
     }
     pub fn eval_to_str(&self, program: &str) -> String {
         let res = self.eval(program);
@@ -39,8 +295,11 @@ impl Katsu {
             })
             .collect()
     }
-    pub fn eval_all(&self, program: &str) -> ResIterator {
-        ResIterator::new(parse(&program), self)
+    pub fn eval_all(&self, program: &str) -> ResIterator<'_> {
+        let ast = self
+            .run(|| parse(program))
+            .unwrap_or_else(|exceeded| panic!("limit exceeded: {}", exceeded));
+        ResIterator::new(ast, self)
     }
 }
 
@@ -61,7 +320,11 @@ impl<'a> ResIterator<'a> {
 impl<'a> Iterator for ResIterator<'a> {
     type Item = String;
     fn next(&mut self) -> Option<String> {
-        let res = interpreter::eval(self.tops.next().unwrap(), &self.katsu.env).unwrap();
+        let top = self.tops.next()?;
+        let res = self
+            .katsu
+            .run(|| interpreter::eval(top, &self.katsu.env).unwrap())
+            .unwrap_or_else(|exceeded| panic!("limit exceeded: {}", exceeded));
         Some(match res {
             Some(v) => format!("{}", v),
             None => String::from("None"),
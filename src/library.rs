@@ -0,0 +1,326 @@
+use crate::interpreter::{self, Copier, Env, Value};
+use crate::parser::*;
+use crate::policy;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+// Libraries
+//
+// Defined libraries are registered by name with the interpreter defining
+// them. The standard libraries are views of the builtins, other libraries are
+// loaded from `<path>/<name parts>.sld` under the search path of the
+// interpreter the first time they are imported.
+
+// What a library exports, by external name
+pub type Exports = Vec<(String, Rc<Value>)>;
+
+// The libraries of an interpreter and where it looks for more
+#[derive(Debug)]
+pub struct Libraries {
+    registry: RefCell<HashMap<LibraryName, Rc<Exports>>>,
+    search_path: RefCell<Vec<PathBuf>>,
+}
+
+impl Default for Libraries {
+    fn default() -> Self {
+        Self {
+            registry: RefCell::new(HashMap::new()),
+            search_path: RefCell::new(vec![PathBuf::from(".")]),
+        }
+    }
+}
+
+impl Libraries {
+    pub fn add_search_path(&self, path: impl Into<PathBuf>) {
+        self.search_path.borrow_mut().push(path.into());
+    }
+    // With the exported values copied, for an interpreter of its own
+    pub fn copy(&self, copier: &mut Copier) -> Self {
+        let registry = self
+            .registry
+            .borrow()
+            .iter()
+            .map(|(name, exports)| {
+                let exports = exports
+                    .iter()
+                    .map(|(name, value)| (name.clone(), copier.value(value)))
+                    .collect();
+                (name.clone(), Rc::new(exports))
+            })
+            .collect();
+        Self {
+            registry: RefCell::new(registry),
+            search_path: self.search_path.clone(),
+        }
+    }
+}
+
+thread_local! {
+    // those of the interpreter evaluating, or of the thread outside of one
+    static CURRENT: RefCell<Rc<Libraries>> = RefCell::new(Rc::new(Libraries::default()));
+}
+
+fn current() -> Rc<Libraries> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+// Run `f` defining and importing libraries with these
+pub fn install<T>(libraries: &Rc<Libraries>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Rc<Libraries>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(saved) = self.0.take() {
+                CURRENT.with(|current| current.replace(saved));
+            }
+        }
+    }
+    let _restore = Restore(Some(CURRENT.with(|current| current.replace(libraries.clone()))));
+    f()
+}
+
+// To the search path of the libraries in use, those of the thread when no
+// interpreter is evaluating
+pub fn add_search_path(path: impl Into<PathBuf>) {
+    current().add_search_path(path);
+}
+
+fn display_name(name: &LibraryName) -> String {
+    format!("({})", name.join(" "))
+}
+
+// The builtins each standard library exports, syntax needs no import since
+// the parser knows every form
+const STANDARD_LIBRARIES: &[(&[&str], &[&str])] = &[
+    (
+        &["scheme", "base"],
+        &[
+            "pair?", "cons", "car", "cdr", "caar", "cadr", "cdar", "cddr", "null?", "list?",
+            "make-list", "list", "length", "append", "reverse", "list-tail", "list-ref",
+            "list-copy", "memq", "memv", "member", "assq", "assv", "assoc", "map", "for-each",
+            "apply", "eq?", "eqv?", "equal?", "number?", "=", "+", "-", "*", "/", "<", ">",
+            "<=", ">=", "zero?", "positive?", "negative?", "abs", "square", "max", "min", "not",
+            "boolean?", "boolean=?", "symbol?", "symbol=?", "procedure?", "values",
+            "call-with-values", "dynamic-wind", "make-parameter", "char?", "char->integer", "integer->char", "char=?", "char<?",
+            "char>?", "char<=?", "char>=?", "string?", "make-string", "string",
+            "string-length", "string-ref", "string-set!", "substring", "string-append",
+            "string-copy", "string-copy!", "string-fill!", "string->list", "list->string",
+            "string=?", "string<?", "string>?", "string<=?", "string>=?", "string-for-each",
+            "string-map", "vector?", "make-vector", "vector", "vector-length", "vector-ref",
+            "vector-set!", "vector->list", "list->vector", "vector->string", "string->vector",
+            "vector-copy", "vector-copy!", "vector-append", "vector-fill!", "vector-map",
+            "vector-for-each", "bytevector?", "make-bytevector", "bytevector",
+            "bytevector-length", "bytevector-u8-ref", "bytevector-u8-set!", "bytevector-copy",
+            "bytevector-copy!", "bytevector-append", "utf8->string", "string->utf8", "port?",
+            "input-port?", "output-port?", "textual-port?", "binary-port?", "input-port-open?",
+            "output-port-open?", "current-input-port", "current-output-port",
+            "current-error-port", "eof-object", "eof-object?", "read-char", "peek-char",
+            "read-line", "read-string", "char-ready?", "read-u8", "peek-u8", "u8-ready?",
+            "write-char", "write-string", "write-u8", "newline", "flush-output-port",
+            "call-with-port", "open-input-string", "open-output-string", "get-output-string",
+            "open-input-bytevector", "open-output-bytevector", "get-output-bytevector",
+            "close-port", "close-input-port", "close-output-port",
+        ],
+    ),
+    (
+        &["scheme", "char"],
+        &[
+            "char-ci=?", "char-ci<?", "char-ci>?", "char-ci<=?", "char-ci>=?",
+            "char-alphabetic?", "char-numeric?", "char-whitespace?", "char-upper-case?",
+            "char-lower-case?", "char-upcase", "char-downcase", "char-foldcase", "digit-value",
+            "string-ci=?", "string-ci<?", "string-ci>?", "string-ci<=?", "string-ci>=?",
+            "string-upcase", "string-downcase", "string-foldcase",
+        ],
+    ),
+    (
+        &["scheme", "cxr"],
+        &[
+            "caaar", "caadr", "cadar", "caddr", "cdaar", "cdadr", "cddar", "cdddr", "caaaar",
+            "caaadr", "caadar", "caaddr", "cadaar", "cadadr", "caddar", "cadddr", "cdaaar",
+            "cdaadr", "cdadar", "cdaddr", "cddaar", "cddadr", "cdddar", "cddddr",
+        ],
+    ),
+    (&["scheme", "write"], &["display", "write", "write-simple", "write-shared"]),
+    (&["scheme", "read"], &["read"]),
+    (&["scheme", "lazy"], &["make-promise", "promise?", "force"]),
+    (&["scheme", "case-lambda"], &[]),
+    (&["scheme", "eval"], &["eval", "environment"]),
+    (
+        &["scheme", "file"],
+        &[
+            "open-input-file", "open-binary-input-file", "open-output-file",
+            "open-binary-output-file", "call-with-input-file", "call-with-output-file",
+            "with-input-from-file", "with-output-to-file", "file-exists?", "delete-file",
+        ],
+    ),
+    // `interaction-environment` is bound in every top-level environment to itself
+    (&["scheme", "repl"], &[]),
+    (&["scheme", "r5rs"], &["scheme-report-environment", "null-environment"]),
+    (
+        &["srfi", "41"],
+        &[
+            "stream?", "stream-null", "stream-null?", "stream-pair?", "stream-car",
+            "stream-cdr", "stream-take", "stream->list", "list->stream",
+        ],
+    ),
+    (
+        &["srfi", "69"],
+        &[
+            "make-hash-table", "hash-table?", "hash-table-ref", "hash-table-ref/default",
+            "hash-table-set!", "hash-table-delete!", "hash-table-exists?",
+            "hash-table-update!", "hash-table-update!/default", "hash-table-size",
+            "hash-table-keys", "hash-table-values", "hash-table->alist", "hash-table-walk",
+            "hash-table-copy", "hash", "string-hash", "hash-by-identity",
+        ],
+    ),
+];
+
+// Looked up in an initialized environment, so a name may be defined in Rust
+// or in the prelude; names the policy denies are left out
+fn standard_library(name: &LibraryName) -> Option<Exports> {
+    let (_, names) = STANDARD_LIBRARIES
+        .iter()
+        .find(|(library, _)| library.iter().eq(name.iter()))?;
+    let env = Env::get_initialized_env();
+    let env = env.borrow();
+    Some(
+        names
+            .iter()
+            .filter_map(|name| Some((name.to_string(), env.resolve(name)?)))
+            .collect(),
+    )
+}
+
+// Evaluate the body of the library in an environment of its own and register
+// its exports; the builtins stay visible there since derived forms expand to
+// calls of them, as far as the policy of the interpreter permits
+pub fn define_library(library: Library) -> Option<()> {
+    let env = Env::get_initialized_env();
+    for top in library.body {
+        interpreter::eval(top, &env).ok()?;
+    }
+    let mut exports = Vec::new();
+    for (internal, external) in library.exports {
+        match env.borrow().resolve(&internal) {
+            Some(value) => exports.push((external, value)),
+            None => {
+                println!("{}: {} is exported but not defined!", display_name(&library.name), internal);
+                return None;
+            }
+        }
+    }
+    current().registry.borrow_mut().insert(library.name, Rc::new(exports));
+    Some(())
+}
+
+fn registered(name: &LibraryName) -> Option<Rc<Exports>> {
+    current().registry.borrow().get(name).cloned()
+}
+
+// Evaluate the first `.sld` file for the library on the search path, which
+// should define it
+fn load_library(name: &LibraryName) -> Option<Rc<Exports>> {
+    let (last, dirs) = name.split_last()?;
+    let file = current().search_path.borrow().iter().find_map(|root| {
+        let mut path = root.clone();
+        path.extend(dirs);
+        path.push(format!("{}.sld", last));
+        // absolute, so it isn't taken as relative to the file importing it
+        path.canonicalize().ok().filter(|path| path.is_file())
+    });
+    let Some(file) = file else {
+        println!("import: library {} not found!", display_name(name));
+        return None;
+    };
+    let env = Env::get_initialized_env();
+    read_source(&file.to_string_lossy(), "import", |program| {
+        for top in parse(&program).tops {
+            interpreter::eval(top, &env).ok()?;
+        }
+        Some(())
+    })?;
+    let exports = registered(name);
+    if exports.is_none() {
+        println!("import: {} does not define {}!", file.display(), display_name(name));
+    }
+    exports
+}
+
+fn find_library(name: &LibraryName) -> Option<Rc<Exports>> {
+    if let Some(exports) = standard_library(name) {
+        return Some(Rc::new(exports));
+    }
+    registered(name).or_else(|| load_library(name))
+}
+
+fn exported(exports: &Exports, identifier: &str, set: &str) -> Option<()> {
+    if exports.iter().any(|(name, _)| name == identifier) {
+        Some(())
+    } else {
+        println!("import: {} is not in the {} set!", identifier, set);
+        None
+    }
+}
+
+// The bindings an import set gives
+fn resolve_set(set: &ImportSet) -> Option<Exports> {
+    match set {
+        ImportSet::LIBRARY(name) => Some((*find_library(name)?).clone()),
+        ImportSet::ONLY(set, identifiers) => {
+            let exports = resolve_set(set)?;
+            for identifier in identifiers {
+                exported(&exports, identifier, "only")?;
+            }
+            Some(
+                exports
+                    .into_iter()
+                    .filter(|(name, _)| identifiers.contains(name))
+                    .collect(),
+            )
+        }
+        ImportSet::EXCEPT(set, identifiers) => {
+            let exports = resolve_set(set)?;
+            for identifier in identifiers {
+                exported(&exports, identifier, "except")?;
+            }
+            Some(
+                exports
+                    .into_iter()
+                    .filter(|(name, _)| !identifiers.contains(name))
+                    .collect(),
+            )
+        }
+        ImportSet::PREFIX(set, prefix) => Some(
+            resolve_set(set)?
+                .into_iter()
+                .map(|(name, value)| (format!("{}{}", prefix, name), value))
+                .collect(),
+        ),
+        ImportSet::RENAME(set, renames) => {
+            let mut exports = resolve_set(set)?;
+            for (from, to) in renames {
+                exported(&exports, from, "rename")?;
+                for (name, _) in exports.iter_mut().filter(|(name, _)| name == from) {
+                    *name = to.clone();
+                }
+            }
+            Some(exports)
+        }
+    }
+}
+
+// Bind what the import sets give in the environment; names sealed there keep
+// their bindings and names the policy denies stay unbound
+pub fn import(env: &Rc<RefCell<Env>>, sets: &[ImportSet]) -> Option<()> {
+    let policy = policy::current();
+    for set in sets {
+        for (name, value) in resolve_set(set)? {
+            if env.borrow().is_sealed_binding(&name) || !policy.permits(&name) {
+                continue;
+            }
+            env.borrow_mut().insert(name, value);
+        }
+    }
+    Some(())
+}
@@ -0,0 +1,232 @@
+use crate::interpreter::Value;
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Limits
+//
+// A budget for evaluation: steps of the evaluator (fuel), nesting of
+// evaluations (depth) and bytes allocated for values (heap). The budget of
+// the running interpreter is kept for the thread, and running out of one
+// unwinds straight to the host with `LimitExceeded`.
+//
+// The heap is an estimate of the bytes allocated since the last reset, memory
+// freed in between is not given back.
+//
+// Evaluation also stops at the next step once its time is up or the host
+// interrupts it from another thread. Builtins blocking on input aren't
+// stopped until they return.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub fuel: Option<u64>,
+    pub depth: Option<usize>,
+    pub heap: Option<usize>,
+    // wall-clock time of each evaluation started by the host
+    pub time: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub steps: u64,
+    pub depth: usize,
+    pub heap: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    FUEL,
+    DEPTH,
+    HEAP,
+    TIME,
+    INTERRUPT,
+}
+
+// What the host gets back instead of results when a limit is hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub usage: Usage,
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            Limit::FUEL => write!(f, "out of fuel at step {}", self.usage.steps),
+            Limit::DEPTH => write!(f, "nested {} levels deep", self.usage.depth),
+            Limit::HEAP => write!(f, "allocated more than {} bytes", self.usage.heap),
+            Limit::TIME => write!(f, "timed out at step {}", self.usage.steps),
+            Limit::INTERRUPT => write!(f, "interrupted at step {}", self.usage.steps),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Budget {
+    pub limits: Limits,
+    pub usage: Usage,
+}
+
+// Stops the evaluation running on another thread; a clone triggers the same
+// interpreter
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    pub fn trigger(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+// The budget installed with when its time runs out
+struct Running {
+    budget: Budget,
+    deadline: Option<Instant>,
+    interrupt: Interrupt,
+}
+
+thread_local! {
+    static BUDGET: RefCell<Option<Running>> = const { RefCell::new(None) };
+}
+
+// How many steps go between looks at the clock
+const CLOCK_STEPS: u64 = 1024;
+
+// Run `f` against the budget, which keeps what `f` used even when it unwinds.
+// A trigger of the interrupt stops `f` even if it came before `f` started, and
+// is dropped once `f` is done so it doesn't reach the next evaluation.
+pub fn install<T>(budget: &RefCell<Budget>, interrupt: &Interrupt, f: impl FnOnce() -> T) -> T {
+    struct Restore<'a> {
+        budget: &'a RefCell<Budget>,
+        interrupt: &'a Interrupt,
+        saved: Option<Running>,
+    }
+    impl Drop for Restore<'_> {
+        fn drop(&mut self) {
+            if let Some(used) = BUDGET.with(|current| current.replace(self.saved.take())) {
+                *self.budget.borrow_mut() = used.budget;
+            }
+            self.interrupt.0.store(false, Ordering::Relaxed);
+        }
+    }
+    let running = Running {
+        budget: *budget.borrow(),
+        deadline: budget.borrow().limits.time.map(|time| Instant::now() + time),
+        interrupt: interrupt.clone(),
+    };
+    let saved = BUDGET.with(|current| current.replace(Some(running)));
+    let _restore = Restore {
+        budget,
+        interrupt,
+        saved,
+    };
+    f()
+}
+
+fn exceeded(limit: Limit, usage: Usage) -> ! {
+    // resume_unwind skips the panic hook, nothing is printed on the way out
+    std::panic::resume_unwind(Box::new(LimitExceeded { limit, usage }))
+}
+
+// Whether the panic is a limit being hit, to tell it from other failures
+pub fn as_exceeded(payload: &(dyn std::any::Any + Send)) -> Option<LimitExceeded> {
+    payload.downcast_ref::<LimitExceeded>().copied()
+}
+
+// One step of the evaluator
+pub fn step() {
+    BUDGET.with(|current| {
+        if let Some(running) = &mut *current.borrow_mut() {
+            let budget = &mut running.budget;
+            budget.usage.steps += 1;
+            if budget.limits.fuel.is_some_and(|fuel| budget.usage.steps > fuel) {
+                exceeded(Limit::FUEL, budget.usage);
+            }
+            if running.interrupt.0.swap(false, Ordering::Relaxed) {
+                exceeded(Limit::INTERRUPT, budget.usage);
+            }
+            if budget.usage.steps % CLOCK_STEPS == 0
+                && running.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                exceeded(Limit::TIME, budget.usage);
+            }
+        }
+    })
+}
+
+// Leaves the level of nesting when dropped
+pub struct Depth;
+
+impl Drop for Depth {
+    fn drop(&mut self) {
+        BUDGET.with(|current| {
+            if let Some(Running { budget, .. }) = &mut *current.borrow_mut() {
+                budget.usage.depth = budget.usage.depth.saturating_sub(1);
+            }
+        })
+    }
+}
+
+pub fn enter() -> Depth {
+    BUDGET.with(|current| {
+        if let Some(Running { budget, .. }) = &mut *current.borrow_mut() {
+            budget.usage.depth += 1;
+            if budget.limits.depth.is_some_and(|depth| budget.usage.depth > depth) {
+                let usage = budget.usage;
+                budget.usage.depth -= 1;
+                exceeded(Limit::DEPTH, usage);
+            }
+        }
+    });
+    Depth
+}
+
+// Fail before making an allocation of this size that would exceed the heap,
+// so a single huge request isn't attempted
+pub fn check(bytes: usize) {
+    BUDGET.with(|current| {
+        if let Some(Running { budget, .. }) = &*current.borrow() {
+            let heap = budget.usage.heap.saturating_add(bytes);
+            if budget.limits.heap.is_some_and(|limit| heap > limit) {
+                exceeded(Limit::HEAP, Usage { heap, ..budget.usage });
+            }
+        }
+    })
+}
+
+pub fn charge(bytes: usize) {
+    BUDGET.with(|current| {
+        if let Some(Running { budget, .. }) = &mut *current.borrow_mut() {
+            budget.usage.heap = budget.usage.heap.saturating_add(bytes);
+            if budget.limits.heap.is_some_and(|heap| budget.usage.heap > heap) {
+                exceeded(Limit::HEAP, budget.usage);
+            }
+        }
+    })
+}
+
+// The bytes of `count` values behind an Rc each
+pub fn size_of_values(count: usize) -> usize {
+    count.saturating_mul(std::mem::size_of::<Value>() + 2 * std::mem::size_of::<usize>())
+}
+
+// The value itself and what it owns directly, the values it refers to are
+// charged where they are made
+pub fn charge_value(value: &Rc<Value>) {
+    let owned = match &**value {
+        Value::PRIMITIVE(primitive) => match &**primitive {
+            crate::parser::Primitive::STRING(string) => string.borrow().len(),
+            _ => 0,
+        },
+        Value::VECTOR(values) => values.borrow().len() * std::mem::size_of::<Rc<Value>>(),
+        Value::BYTEVECTOR(bytes) => bytes.borrow().len(),
+        Value::VALUES(values) => values.len() * std::mem::size_of::<Rc<Value>>(),
+        _ => 0,
+    };
+    charge(size_of_values(1) + owned);
+}
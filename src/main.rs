@@ -1,17 +1,10 @@
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
-
-mod parser; 
-mod interpreter;
-mod builtin;
-
 use clap::Parser;
-use interpreter::{Env, Value};
+use katsu::parser;
+use katsu::Katsu;
 use std::io::Write;
 use std::io::{stdin, stdout};
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 #[derive(Debug, clap::Parser)]
 #[clap(version)]
@@ -21,50 +14,85 @@ struct Opt {
 
     #[clap(short = 'i', long = "interactive", help = "interactive mode")]
     interactive: bool,
+
+    #[clap(short = 'L', long = "library-path", help = "directory searched for libraries")]
+    library_path: Vec<String>,
 }
 
-fn repl() {
+fn repl(katsu: Katsu) {
     let mut line = String::new();
-    let env = interpreter::Env::get_initialized_env();
+
+    // Ctrl-C stops what is being evaluated and goes back to the prompt, at
+    // the prompt it quits
+    let evaluating = Arc::new(AtomicBool::new(false));
+    let interrupt = katsu.interrupt_handle();
+    let running = evaluating.clone();
+    ctrlc::set_handler(move || {
+        if running.load(Ordering::Relaxed) {
+            interrupt.trigger();
+        } else {
+            std::process::exit(130);
+        }
+    })
+    .expect("the Ctrl-C handler is set");
+
+    // a failing line prints its error and goes back to the prompt as well
+    std::panic::set_hook(Box::new(|info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| payload.downcast_ref::<&str>().copied())
+            .unwrap_or("error");
+        println!("{}", message);
+    }));
 
     loop {
         print!("> ");
         stdout().flush().unwrap();
-        stdin().read_line(&mut line).unwrap();
-        if line.trim().eq("quit") || line.eq("q") {
+        line.clear();
+        if stdin().read_line(&mut line).unwrap() == 0 {
             break;
-        } else if line.is_empty() {
+        }
+        if line.trim().eq("quit") || line.trim().eq("q") {
+            break;
+        } else if line.trim().is_empty() {
             continue;
         }
-        let res = run(&line, &env);
-        if let Some(res) = res {
-            println!("{}", res);
-        } else {
-            println!("No result");
+        evaluating.store(true, Ordering::Relaxed);
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| katsu.try_eval(&line)));
+        evaluating.store(false, Ordering::Relaxed);
+        match res {
+            Ok(Ok(res)) => match res.last() {
+                Some(Some(res)) => println!("{}", res),
+                _ => println!("No result"),
+            },
+            Ok(Err(exceeded)) => println!("{}", exceeded),
+            // the hook has printed what went wrong
+            Err(_) => {}
         }
     }
 }
 
-fn run(program: &str, env: &Rc<RefCell<Env>>) -> Option<Rc<Value>> {
-    let ast = parser::parse(&program);
-    let mut res = None;
-    for top in ast.tops {
-        res = interpreter::eval(top, env).unwrap();
-    }
-    res
-}
-
 fn main() {
     let opt = Opt::parse();
+    let mut builder = Katsu::builder();
+    for path in &opt.library_path {
+        builder = builder.library_path(path);
+    }
 
     if let Some(file) = opt.file {
-        let program = std::fs::read_to_string(file).unwrap();
-        let env = interpreter::Env::get_initialized_env();
-        run(&program, &env);
+        // libraries next to the script come after those given explicitly
+        if let Some(dir) = std::path::Path::new(&file).parent() {
+            builder = builder.library_path(dir);
+        }
+        let katsu = builder.build();
+        // includes and loads in the script are relative to it
+        parser::read_source(&file, "katsu", |program| katsu.eval(&program));
     } else if opt.interactive {
-        repl();
+        repl(builder.build());
     } else {
-        repl();
+        repl(builder.build());
         println!("No input file or interactive mode");
     }
 }
@@ -1,4 +1,7 @@
+use crate::policy;
+use std::cell::RefCell;
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 mod syu_parser;
@@ -16,6 +19,54 @@ pub fn parse(input: &str) -> Ast {
     }
 }
 
+thread_local! {
+    // The files being read, the innermost last
+    static SOURCES: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+// Relative paths are taken from the directory of the file being read, or the
+// working directory outside of any
+pub fn resolve_path(path: &str) -> PathBuf {
+    SOURCES.with(|sources| match sources.borrow().last() {
+        Some(source) => source.parent().unwrap_or(Path::new("")).join(path),
+        None => PathBuf::from(path),
+    })
+}
+
+// Call `f` with the text of the file, which is the file being read until `f`
+// returns; a file may not be read again from within itself
+pub fn read_source<T>(path: &str, name: &str, f: impl FnOnce(String) -> T) -> T {
+    struct Pop;
+    impl Drop for Pop {
+        fn drop(&mut self) {
+            SOURCES.with(|sources| sources.borrow_mut().pop());
+        }
+    }
+    policy::check_files(name);
+    let resolved = resolve_path(path);
+    let resolved = resolved
+        .canonicalize()
+        .unwrap_or_else(|e| panic!("{}: {}: {}", name, resolved.display(), e));
+    if SOURCES.with(|sources| sources.borrow().contains(&resolved)) {
+        panic!("{}: {} includes itself", name, resolved.display());
+    }
+    let text = std::fs::read_to_string(&resolved)
+        .unwrap_or_else(|e| panic!("{}: {}: {}", name, resolved.display(), e));
+    SOURCES.with(|sources| sources.borrow_mut().push(resolved));
+    let _pop = Pop;
+    f(text)
+}
+
+// The builtin sources are written for the pest grammar, whatever PARSER says
+pub fn parse_pest(input: &str) -> Ast {
+    pest_parser::PestParser::new().parse(input)
+}
+
+// Read the text of one datum, as `read` does; None when it isn't one
+pub fn parse_datum(text: &str) -> Option<Rc<Datum>> {
+    pest_parser::PestParser::new().parse_datum(text).map(Rc::new)
+}
+
 #[derive(Debug)]
 pub struct Ast {
     pub tops: Vec<Top>,
@@ -30,12 +81,37 @@ impl Ast {
 #[derive(Debug)]
 pub enum Top {
     DEC {
-        identifier: String,
+        target: Target,
         expression: Rc<Exp>,
     },
     EXP {
         expression: Rc<Exp>,
     },
+    IMPORT(Vec<ImportSet>),
+    LIBRARY(Library),
+}
+
+// A library name with the parts as written, `(srfi 1)` is ["srfi", "1"]
+pub type LibraryName = Vec<String>;
+
+#[derive(Debug)]
+pub enum ImportSet {
+    LIBRARY(LibraryName),
+    ONLY(Box<ImportSet>, Vec<String>),
+    EXCEPT(Box<ImportSet>, Vec<String>),
+    PREFIX(Box<ImportSet>, String),
+    // (from, to)
+    RENAME(Box<ImportSet>, Vec<(String, String)>),
+}
+
+// The imports and definitions of `begin` declarations are kept as the tops
+// of a program, in order
+#[derive(Debug)]
+pub struct Library {
+    pub name: LibraryName,
+    // (internal, external)
+    pub exports: Vec<(String, String)>,
+    pub body: Vec<Top>,
 }
 
 #[derive(Debug)]
@@ -57,7 +133,11 @@ pub enum Exp {
 pub enum Datum {
     PRIMITIVE(Rc<Primitive>),
     LAMBDA(Rc<Lambda>),
-    PAIR((Option<Rc<Datum>>, Option<Rc<Datum>>)),
+    // One lambda per clause, the first whose formals accept the arguments is called
+    CASELAMBDA(Vec<Rc<Lambda>>),
+    PAIR((Rc<Datum>, Rc<Datum>)),
+    VECTOR(Vec<Rc<Datum>>),
+    BYTEVECTOR(Vec<u8>),
 }
 
 // primitives are shared by compiler and interpreter
@@ -65,7 +145,8 @@ pub enum Datum {
 pub enum Primitive {
     NUMBER(i64),
     BOOLEAN(bool),
-    STRING(String),
+    CHAR(char),
+    STRING(RefCell<String>),
     SYMBOL(String),
     NIL,
 }
@@ -74,11 +155,36 @@ impl Display for Datum {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Datum::LAMBDA(lambda) => write!(f, "#<lambda {:?}>", lambda),
+            Datum::CASELAMBDA(clauses) => write!(f, "#<case-lambda {:?}>", clauses),
 
-            Datum::PAIR((Some(car), Some(cdr))) => write!(f, "({} . {})", car, cdr),
-            Datum::PAIR((Some(car), None)) => write!(f, "({})", car),
-            Datum::PAIR((None, Some(cdr))) => write!(f, "({})", cdr),
-            Datum::PAIR((None, None)) => write!(f, "()"),
+            Datum::PAIR((car, cdr)) => {
+                write!(f, "({}", car)?;
+                let mut rest = cdr.clone();
+                loop {
+                    rest = match &*rest {
+                        Datum::PAIR((car, cdr)) => {
+                            write!(f, " {}", car)?;
+                            cdr.clone()
+                        }
+                        Datum::PRIMITIVE(primitive) if matches!(**primitive, Primitive::NIL) => {
+                            break
+                        }
+                        _ => {
+                            write!(f, " . {}", rest)?;
+                            break;
+                        }
+                    }
+                }
+                write!(f, ")")
+            }
+            Datum::VECTOR(data) => {
+                let data: Vec<String> = data.iter().map(|datum| datum.to_string()).collect();
+                write!(f, "#({})", data.join(" "))
+            }
+            Datum::BYTEVECTOR(bytes) => {
+                let bytes: Vec<String> = bytes.iter().map(|byte| byte.to_string()).collect();
+                write!(f, "#u8({})", bytes.join(" "))
+            }
             Datum::PRIMITIVE(primitive) => write!(f, "{}", primitive),
         }
     }
@@ -89,17 +195,34 @@ impl Display for Primitive {
         match self {
             Primitive::NUMBER(number) => write!(f, "{}", number),
             Primitive::BOOLEAN(boolean) => write!(f, "{}", boolean),
-            Primitive::STRING(string) => write!(f, "{}", string),
+            Primitive::CHAR(c) => write!(f, "{}", c),
+            Primitive::STRING(string) => write!(f, "{}", string.borrow()),
             Primitive::SYMBOL(symbol) => write!(f, "{}", symbol),
-            Primitive::NIL => write!(f, "NIL"),
+            Primitive::NIL => write!(f, "()"),
         }
     }
 }
 
+// The required parameters, then the one bound to a list of the remaining values
 #[derive(Debug)]
-pub struct Lambda {
-    pub ifvarlen: bool,
+pub struct Formals {
     pub parameters: Vec<String>,
-    pub definitions: Vec<(String, Rc<Exp>)>,
-    pub body: Rc<Exp>,
+    pub rest: Option<String>,
+}
+
+// What a definition binds: `(define x ...)` binds the value itself,
+// `(define-values formals ...)` spreads multiple values over the formals
+#[derive(Debug)]
+pub enum Target {
+    IDENTIFIER(String),
+    FORMALS(Formals),
+}
+
+pub type Definitions = Vec<(Target, Rc<Exp>)>;
+
+#[derive(Debug)]
+pub struct Lambda {
+    pub formals: Formals,
+    pub definitions: Definitions,
+    pub body: Vec<Rc<Exp>>,
 }
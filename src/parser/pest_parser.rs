@@ -4,6 +4,7 @@ use pest::Parser;
 pub struct PestParser;
 
 use super::*;
+use std::cell::RefCell;
 
 #[derive(Parser)]
 #[grammar = "parser/scheme.pest"]
@@ -25,8 +26,13 @@ macro_rules! handle_literals {
                 }
             }
             Rule::string => {
-                let string = $item.as_str().to_string();
-                return Some(Datum::PRIMITIVE(Rc::new(Primitive::STRING(string))));
+                let text = $item.as_str();
+                let string = build_string(&text[1..text.len() - 1])?;
+                return Some(Datum::PRIMITIVE(Rc::new(Primitive::STRING(RefCell::new(string)))));
+            }
+            Rule::character => {
+                let character = build_char(&$item.as_str()[2..])?;
+                return Some(Datum::PRIMITIVE(Rc::new(Primitive::CHAR(character))));
             }
             _ => unreachable!(),
         }
@@ -45,6 +51,243 @@ macro_rules! inner2 {
     };
 }
 
+// Decode the text after `#\` of a character literal
+fn build_char(text: &str) -> Option<char> {
+    match text {
+        "alarm" => Some('\u{7}'),
+        "backspace" => Some('\u{8}'),
+        "delete" => Some('\u{7f}'),
+        "escape" => Some('\u{1b}'),
+        "newline" => Some('\n'),
+        "null" => Some('\0'),
+        "return" => Some('\r'),
+        "space" => Some(' '),
+        "tab" => Some('\t'),
+        _ if text.len() > 1 && text.starts_with('x') => {
+            char::from_u32(u32::from_str_radix(&text[1..], 16).ok()?)
+        }
+        _ => text.chars().next(),
+    }
+}
+
+// Decode the escape sequences between the quotes of a string literal
+fn build_string(text: &str) -> Option<String> {
+    let mut string = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'a' => string.push('\u{7}'),
+            'b' => string.push('\u{8}'),
+            't' => string.push('\t'),
+            'n' => string.push('\n'),
+            'r' => string.push('\r'),
+            'x' => {
+                let hex: String = chars.by_ref().take_while(|c| *c != ';').collect();
+                string.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            // \<intraline whitespace>*<line ending><intraline whitespace>*
+            mut c @ (' ' | '\t' | '\n' | '\r') => {
+                while c == ' ' || c == '\t' {
+                    c = chars.next()?;
+                }
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                while let Some(' ' | '\t') = chars.peek() {
+                    chars.next();
+                }
+            }
+            c => string.push(c),
+        }
+    }
+    Some(string)
+}
+
+// Lower the case of a program outside its string literals, as if read after
+// `#!fold-case`; the character after `#\` keeps its case
+fn fold_case(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            '\\' if in_string => {
+                folded.push(c);
+                folded.extend(chars.next());
+                continue;
+            }
+            '#' if !in_string && chars.peek() == Some(&'\\') => {
+                folded.push(c);
+                folded.extend(chars.next());
+                folded.extend(chars.next());
+                continue;
+            }
+            _ => (),
+        }
+        if in_string {
+            folded.push(c);
+        } else {
+            folded.extend(c.to_lowercase());
+        }
+    }
+    folded
+}
+
+fn lambda(formals: Formals, definitions: Definitions, body: Vec<Rc<Exp>>) -> Exp {
+    Exp::LITERIAL(Rc::new(Datum::LAMBDA(Rc::new(Lambda {
+        formals,
+        definitions,
+        body,
+    }))))
+}
+
+fn fixed(parameters: Vec<String>) -> Formals {
+    Formals {
+        parameters,
+        rest: None,
+    }
+}
+
+// ((lambda (t ...) (define-values formals t) ... body) e ...), the temporaries
+// can't be written in source, so they never capture a user identifier
+fn let_values(bindings: Vec<(Formals, Rc<Exp>)>, definitions: Definitions, body: Vec<Rc<Exp>>) -> Exp {
+    let mut temporaries = Vec::new();
+    let mut destructures = Vec::new();
+    let mut operands = Vec::new();
+    for (i, (formals, expression)) in bindings.into_iter().enumerate() {
+        let temporary = format!(" values {}", i);
+        destructures.push((
+            Target::FORMALS(formals),
+            Rc::new(Exp::IDENTIFIER(temporary.clone())),
+        ));
+        temporaries.push(temporary);
+        operands.push(expression);
+    }
+    destructures.extend(definitions);
+    Exp::CALL {
+        operator: Rc::new(lambda(fixed(temporaries), destructures, body)),
+        operands,
+    }
+}
+
+fn abbreviation(prefix: &str) -> &'static str {
+    match prefix {
+        "'" => "quote",
+        "`" => "quasiquote",
+        "," => "unquote",
+        ",@" => "unquote-splicing",
+        _ => unreachable!(),
+    }
+}
+
+fn symbol(name: &str) -> Datum {
+    Datum::PRIMITIVE(Rc::new(Primitive::SYMBOL(name.to_string())))
+}
+
+fn list(data: Vec<Datum>) -> Datum {
+    data.into_iter()
+        .rev()
+        .fold(Datum::PRIMITIVE(Rc::new(Primitive::NIL)), |cdr, car| {
+            Datum::PAIR((Rc::new(car), Rc::new(cdr)))
+        })
+}
+
+fn call(procedure: &str, operands: Vec<Exp>) -> Exp {
+    Exp::CALL {
+        operator: Rc::new(Exp::IDENTIFIER(procedure.to_string())),
+        operands: operands.into_iter().map(Rc::new).collect(),
+    }
+}
+
+// Constant parts of a template stay literal, so a template without unquotes
+// costs no more than a quotation
+fn cons(car: Exp, cdr: Exp) -> Exp {
+    match (car, cdr) {
+        (Exp::LITERIAL(car), Exp::LITERIAL(cdr)) => Exp::LITERIAL(Rc::new(Datum::PAIR((car, cdr)))),
+        (car, cdr) => call(" cons", vec![car, cdr]),
+    }
+}
+
+// Names the expansions bind, they can't be written in source so they never
+// capture a user identifier
+const TEMPORARY: &str = " t";
+const DO_LOOP: &str = " do";
+
+fn temporary() -> Rc<Exp> {
+    Rc::new(Exp::IDENTIFIER(TEMPORARY.to_string()))
+}
+
+// ((lambda (t) body) value)
+fn let_temporary(value: Rc<Exp>, body: Exp) -> Exp {
+    Exp::CALL {
+        operator: Rc::new(lambda(fixed(vec![TEMPORARY.to_string()]), Vec::new(), vec![Rc::new(body)])),
+        operands: vec![value],
+    }
+}
+
+// ((letrec ((name (lambda (x ...) body))) name) e ...)
+fn named_let(
+    name: String,
+    bindings: Vec<(String, Rc<Exp>)>,
+    definitions: Definitions,
+    body: Vec<Rc<Exp>>,
+) -> Exp {
+    let (parameters, operands) = bindings.into_iter().unzip();
+    let procedure = lambda(fixed(parameters), definitions, body);
+    let letrec = lambda(
+        fixed(Vec::new()),
+        vec![(Target::IDENTIFIER(name.clone()), Rc::new(procedure))],
+        vec![Rc::new(Exp::IDENTIFIER(name))],
+    );
+    Exp::CALL {
+        operator: Rc::new(Exp::CALL {
+            operator: Rc::new(letrec),
+            operands: Vec::new(),
+        }),
+        operands,
+    }
+}
+
+fn if_exp(test: Rc<Exp>, consequent: Rc<Exp>, alternative: Option<Rc<Exp>>) -> Exp {
+    Exp::COND {
+        test,
+        consequent,
+        alternative,
+    }
+}
+
+fn literal(primitive: Primitive) -> Rc<Exp> {
+    Rc::new(Exp::LITERIAL(Rc::new(Datum::PRIMITIVE(Rc::new(primitive)))))
+}
+
+// (constructor (lambda () expression))
+fn promise(constructor: &str, expression: Exp) -> Exp {
+    let thunk = lambda(fixed(Vec::new()), Vec::new(), vec![Rc::new(expression)]);
+    call(constructor, vec![thunk])
+}
+
+// `(if #f #f)`
+fn unspecified() -> Exp {
+    let false_ = literal(Primitive::BOOLEAN(false));
+    if_exp(false_.clone(), false_, None)
+}
+
+// ((lambda () e ...)) unless there is a single expression
+fn sequence(mut body: Vec<Rc<Exp>>) -> Rc<Exp> {
+    if body.len() == 1 {
+        return body.pop().unwrap();
+    }
+    Rc::new(Exp::CALL {
+        operator: Rc::new(lambda(fixed(Vec::new()), Vec::new(), body)),
+        operands: Vec::new(),
+    })
+}
+
 impl PestParser {
     pub fn new() -> Self {
         Self {}
@@ -54,20 +297,20 @@ impl PestParser {
         let pair = inner1!(pair);
         match pair.as_rule() {
             Rule::pure_list => {
-                let mut pairs = pair.into_inner().rev();
+                let pairs = pair.into_inner().rev();
                 let mut curr = Datum::PRIMITIVE(Rc::new(Primitive::NIL));
-                while let Some(p) = pairs.next() {
+                for p in pairs {
                     let datum = self.build_datum(p)?;
-                    curr = Datum::PAIR((Some(Rc::new(datum)), Some(Rc::new(curr))));
+                    curr = Datum::PAIR((Rc::new(datum), Rc::new(curr)));
                 }
                 Some(curr)
             }
             Rule::list_pair => {
                 let mut pairs = pair.into_inner().rev();
                 let mut curr = self.build_datum(pairs.next()?)?;
-                while let Some(p) = pairs.next() {
+                for p in pairs {
                     let datum = self.build_datum(p)?;
-                    curr = Datum::PAIR((Some(Rc::new(datum)), Some(Rc::new(curr))));
+                    curr = Datum::PAIR((Rc::new(datum), Rc::new(curr)));
                 }
                 Some(curr)
             }
@@ -75,17 +318,38 @@ impl PestParser {
         }
     }
 
+    fn build_vector(&self, pair: Pair<Rule>) -> Option<Datum> {
+        match pair.as_rule() {
+            Rule::vector => {
+                let mut data = Vec::new();
+                for p in pair.into_inner() {
+                    data.push(Rc::new(self.build_datum(p)?));
+                }
+                Some(Datum::VECTOR(data))
+            }
+            Rule::bytevector => {
+                let mut bytes = Vec::new();
+                for p in pair.into_inner() {
+                    bytes.push(p.as_str().parse::<u8>().ok()?);
+                }
+                Some(Datum::BYTEVECTOR(bytes))
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn build_datum(&self, pair: Pair<Rule>) -> Option<Datum> {
         match pair.as_rule() {
+            Rule::datum => self.build_datum(inner1!(pair)),
             Rule::simple_datum => {
                 let simple_datum = inner1!(pair);
                 match simple_datum.as_rule() {
-                    Rule::number | Rule::boolean | Rule::string => {
+                    Rule::number | Rule::boolean | Rule::character | Rule::string => {
                         handle_literals!(simple_datum);
                     }
                     Rule::symbol => {
                         let symbol = simple_datum.as_str().to_string();
-                        return Some(Datum::PRIMITIVE(Rc::new(Primitive::SYMBOL(symbol))));
+                        Some(Datum::PRIMITIVE(Rc::new(Primitive::SYMBOL(symbol))))
                     }
                     _ => unreachable!(),
                 }
@@ -94,9 +358,12 @@ impl PestParser {
                 let compound_datum = inner1!(pair);
                 match compound_datum.as_rule() {
                     Rule::list => self.build_compounds(compound_datum),
+                    Rule::vector | Rule::bytevector => self.build_vector(compound_datum),
                     Rule::abbr => {
-                        let abbr = inner1!(compound_datum);
-                        self.build_datum(abbr)
+                        let mut pairs = compound_datum.into_inner();
+                        let keyword = abbreviation(pairs.next()?.as_str());
+                        let datum = self.build_datum(pairs.next()?)?;
+                        Some(list(vec![symbol(keyword), datum]))
                     }
                     _ => unreachable!(),
                 }
@@ -108,16 +375,117 @@ impl PestParser {
     fn build_literal(&self, pair: Pair<Rule>) -> Option<Datum> {
         match pair.as_rule() {
             Rule::quotation => {
-                return self.build_datum(inner1!(pair));
+                self.build_datum(inner1!(pair))
             }
             Rule::self_evaluating => {
                 let literal = inner1!(pair);
-                handle_literals!(literal);
+                match literal.as_rule() {
+                    Rule::vector | Rule::bytevector => self.build_vector(literal),
+                    _ => handle_literals!(literal),
+                }
             }
             _ => unreachable!(),
         }
     }
 
+    // `(quasiquote x)`, `(unquote x)`... written either way: the keyword and x
+    fn build_quasi_form<'a>(&self, pair: &Pair<'a, Rule>) -> Option<(&'static str, Pair<'a, Rule>)> {
+        let compound = pair.clone().into_inner().next()?;
+        if compound.as_rule() != Rule::compound_datum {
+            return None;
+        }
+        let inner = inner1!(compound);
+        match inner.as_rule() {
+            Rule::abbr => {
+                let mut pairs = inner.into_inner();
+                let keyword = abbreviation(pairs.next()?.as_str());
+                Some((keyword, pairs.next()?))
+            }
+            Rule::list => {
+                let list = inner1!(inner);
+                if list.as_rule() != Rule::pure_list {
+                    return None;
+                }
+                let data: Vec<_> = list.into_inner().collect();
+                let keyword = ["quote", "quasiquote", "unquote", "unquote-splicing"]
+                    .into_iter()
+                    .find(|keyword| data.len() == 2 && data[0].as_str() == *keyword)?;
+                Some((keyword, data[1].clone()))
+            }
+            _ => None,
+        }
+    }
+
+    // Expand the template `pair` nested in `depth` quasiquotes into list construction
+    fn build_quasi(&self, pair: Pair<Rule>, depth: usize) -> Option<Exp> {
+        if let Some((keyword, template)) = self.build_quasi_form(&pair) {
+            let depth = match keyword {
+                "quasiquote" => depth + 1,
+                "unquote" if depth == 1 => {
+                    let exp = PestDriver::parse(Rule::exp, template.as_str()).ok()?.next()?;
+                    return self.build_exp(exp);
+                }
+                "unquote" | "unquote-splicing" => depth
+                    .checked_sub(1)
+                    .unwrap_or_else(|| panic!("quasiquote: {} outside of a quasiquote", keyword)),
+                _ => depth,
+            };
+            let quoted = self.build_quasi(template, depth)?;
+            let nil = Exp::LITERIAL(Rc::new(list(Vec::new())));
+            return Some(cons(
+                Exp::LITERIAL(Rc::new(symbol(keyword))),
+                cons(quoted, nil),
+            ));
+        }
+        let inner = inner1!(pair.clone());
+        let compound = match inner.as_rule() {
+            Rule::compound_datum => inner1!(inner),
+            _ => return Some(Exp::LITERIAL(Rc::new(self.build_datum(pair)?))),
+        };
+        match compound.as_rule() {
+            Rule::list => {
+                let list = inner1!(compound);
+                let mut data: Vec<_> = list.clone().into_inner().collect();
+                let mut tail = match list.as_rule() {
+                    Rule::list_pair => self.build_quasi(data.pop()?, depth)?,
+                    _ => Exp::LITERIAL(Rc::new(self::list(Vec::new()))),
+                };
+                for datum in data.into_iter().rev() {
+                    tail = self.build_quasi_element(datum, tail, depth)?;
+                }
+                Some(tail)
+            }
+            Rule::vector => {
+                let mut elements = Exp::LITERIAL(Rc::new(list(Vec::new())));
+                for datum in compound.clone().into_inner().rev() {
+                    elements = self.build_quasi_element(datum, elements, depth)?;
+                }
+                match elements {
+                    Exp::LITERIAL(_) => Some(Exp::LITERIAL(Rc::new(self.build_vector(compound)?))),
+                    elements => Some(call(" list->vector", vec![elements])),
+                }
+            }
+            _ => Some(Exp::LITERIAL(Rc::new(self.build_datum(pair)?))),
+        }
+    }
+
+    // Prepend the element `pair` of a list template to `tail`, splicing `,@x`
+    fn build_quasi_element(&self, pair: Pair<Rule>, tail: Exp, depth: usize) -> Option<Exp> {
+        match self.build_quasi_form(&pair) {
+            Some(("unquote-splicing", template)) if depth == 1 => {
+                let exp = PestDriver::parse(Rule::exp, template.as_str()).ok()?.next()?;
+                let spliced = self.build_exp(exp)?;
+                match &tail {
+                    Exp::LITERIAL(datum) if matches!(&**datum, Datum::PRIMITIVE(p) if **p == Primitive::NIL) => {
+                        Some(spliced)
+                    }
+                    _ => Some(call(" append", vec![spliced, tail])),
+                }
+            }
+            _ => Some(cons(self.build_quasi(pair, depth)?, tail)),
+        }
+    }
+
     fn build_call(&self, pair: Pair<Rule>) -> Option<Exp> {
         let mut pairs = pair.into_inner();
         let operator = self.build_exp(inner2!(pairs.next()?))?;
@@ -133,26 +501,220 @@ impl PestParser {
 
     fn build_lambda(&self, pair: Pair<Rule>) -> Option<Exp> {
         let mut pairs = pair.into_inner();
-        let mut parameters = Vec::new();
-        let formal = inner1!(pairs.next()?);
-        let ifvarlen = formal.as_rule() == Rule::varlen;
-        for pair in formal.into_inner() {
-            parameters.push(pair.as_str().to_string());
+        let formals = self.build_formals(pairs.next()?)?;
+        let (definitions, body) = self.build_body(pairs.next()?)?;
+        Some(lambda(formals, definitions, body))
+    }
+
+    fn build_formals(&self, pair: Pair<Rule>) -> Option<Formals> {
+        let formal = inner1!(pair);
+        match formal.as_rule() {
+            Rule::varlen => Some(Formals {
+                parameters: Vec::new(),
+                rest: Some(formal.as_str().to_string()),
+            }),
+            Rule::fixlen => self.build_parameters(formal.into_inner()),
+            _ => unreachable!(),
         }
+    }
+
+    // Identifiers optionally followed by a dotted rest parameter
+    fn build_parameters(&self, pairs: Pairs<Rule>) -> Option<Formals> {
+        let mut formals = fixed(Vec::new());
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::identifier => formals.parameters.push(pair.as_str().to_string()),
+                Rule::rest => formals.rest = Some(inner1!(pair).as_str().to_string()),
+                _ => unreachable!(),
+            }
+        }
+        Some(formals)
+    }
+
+    fn build_case_lambda(&self, pair: Pair<Rule>) -> Option<Exp> {
+        let mut clauses = Vec::new();
+        for clause in pair.into_inner() {
+            let mut pairs = clause.into_inner();
+            let formals = self.build_formals(pairs.next()?)?;
+            let (definitions, body) = self.build_body(pairs.next()?)?;
+            clauses.push(Rc::new(Lambda {
+                formals,
+                definitions,
+                body,
+            }));
+        }
+        Some(Exp::LITERIAL(Rc::new(Datum::CASELAMBDA(clauses))))
+    }
+
+    // Internal definitions followed by a sequence of expressions
+    fn build_body(&self, pair: Pair<Rule>) -> Option<(Definitions, Vec<Rc<Exp>>)> {
+        let mut pairs = pair.into_inner();
         let mut definitions = Vec::new();
         for pair in pairs.next()?.into_inner() {
+            definitions.push(self.build_definition(pair)?);
+        }
+        let mut body = Vec::new();
+        for pair in pairs {
+            body.push(Rc::new(self.build_exp(pair)?));
+        }
+        Some((definitions, body))
+    }
+
+    fn build_definition(&self, pair: Pair<Rule>) -> Option<(Target, Rc<Exp>)> {
+        let mut pairs = pair.into_inner();
+        let first = pairs.next()?;
+        match first.as_rule() {
+            Rule::define_values => {
+                let mut pairs = first.into_inner();
+                let formals = self.build_formals(pairs.next()?)?;
+                let expression = Rc::new(self.build_exp(pairs.next()?)?);
+                Some((Target::FORMALS(formals), expression))
+            }
+            Rule::define_record_type => self.build_record_type(first),
+            Rule::identifier => {
+                let identifier = first.as_str().to_string();
+                let expression = Rc::new(self.build_exp(pairs.next()?)?);
+                Some((Target::IDENTIFIER(identifier), expression))
+            }
+            Rule::def_formals => {
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                self.build_procedure(first, definitions, body)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // (define-values (type constructor predicate accessor [modifier] ...)
+    //   ((lambda (t) (values t (lambda (field ...) (record t field ...)) ...))
+    //    (record-type 'type '(field ...))))
+    // with `values` and the record builtins under their internal names
+    fn build_record_type(&self, pair: Pair<Rule>) -> Option<(Target, Rc<Exp>)> {
+        let mut pairs = pair.into_inner();
+        let type_name = pairs.next()?.as_str().to_string();
+        let mut constructor = pairs.next()?.into_inner().map(|pair| pair.as_str().to_string());
+        let constructor_name = constructor.next()?;
+        let arguments: Vec<String> = constructor.collect();
+        let predicate_name = pairs.next()?.as_str().to_string();
+        let specs: Vec<Vec<String>> = pairs
+            .map(|spec| spec.into_inner().map(|pair| pair.as_str().to_string()).collect())
+            .collect();
+        let fields: Vec<String> = specs.iter().map(|spec| spec[0].clone()).collect();
+
+        let rtd = || Exp::IDENTIFIER(TEMPORARY.to_string());
+        let quoted = |datum| Exp::LITERIAL(Rc::new(datum));
+        let index = |i: usize| quoted(Datum::PRIMITIVE(Rc::new(Primitive::NUMBER(i as i64))));
+        let procedure = |parameters: Vec<&str>, body| {
+            lambda(
+                fixed(parameters.into_iter().map(str::to_string).collect()),
+                Vec::new(),
+                vec![Rc::new(body)],
+            )
+        };
+
+        let mut names = vec![type_name.clone(), constructor_name, predicate_name];
+        let mut values = vec![rtd()];
+        // fields the constructor doesn't take start out unspecified
+        let mut initials = vec![rtd()];
+        for field in &fields {
+            initials.push(match arguments.contains(field) {
+                true => Exp::IDENTIFIER(field.clone()),
+                false => unspecified(),
+            });
+        }
+        values.push(procedure(
+            arguments.iter().map(String::as_str).collect(),
+            call(" record", initials),
+        ));
+        values.push(procedure(vec!["obj"], call(" record?", vec![Exp::IDENTIFIER("obj".to_string()), rtd()])));
+        for (i, spec) in specs.iter().enumerate() {
+            let obj = || Exp::IDENTIFIER("obj".to_string());
+            names.push(spec[1].clone());
+            values.push(procedure(vec!["obj"], call(" record-ref", vec![obj(), rtd(), index(i)])));
+            if let Some(modifier) = spec.get(2) {
+                names.push(modifier.clone());
+                let value = Exp::IDENTIFIER("value".to_string());
+                values.push(procedure(
+                    vec!["obj", "value"],
+                    call(" record-set!", vec![obj(), rtd(), index(i), value]),
+                ));
+            }
+        }
+
+        // `<point>` is conventionally written for the type `point`
+        let name = type_name.trim_start_matches('<').trim_end_matches('>');
+        let descriptor = call(
+            " record-type",
+            vec![
+                quoted(symbol(name)),
+                quoted(list(fields.iter().map(|field| symbol(field)).collect())),
+            ],
+        );
+        let expression = let_temporary(Rc::new(descriptor), call(" values", values));
+        Some((Target::FORMALS(fixed(names)), Rc::new(expression)))
+    }
+
+    // (define ((f a) b) body) is (define (f a) (lambda (b) body))
+    fn build_procedure(
+        &self,
+        pair: Pair<Rule>,
+        definitions: Definitions,
+        body: Vec<Rc<Exp>>,
+    ) -> Option<(Target, Rc<Exp>)> {
+        let mut pairs = pair.into_inner();
+        let head = pairs.next()?;
+        let formals = self.build_parameters(pairs)?;
+        let procedure = lambda(formals, definitions, body);
+        match head.as_rule() {
+            Rule::identifier => Some((
+                Target::IDENTIFIER(head.as_str().to_string()),
+                Rc::new(procedure),
+            )),
+            Rule::def_formals => self.build_procedure(head, Vec::new(), vec![Rc::new(procedure)]),
+            _ => unreachable!(),
+        }
+    }
+
+    // A sequence of expressions evaluated for the value of the last one
+    fn build_sequence<'a>(&self, pairs: impl Iterator<Item = Pair<'a, Rule>>) -> Option<Rc<Exp>> {
+        let mut body = Vec::new();
+        for pair in pairs {
+            body.push(Rc::new(self.build_exp(pair)?));
+        }
+        Some(sequence(body))
+    }
+
+    // The body of a case clause, `=> receiver` is called with the key
+    fn build_case_body<'a>(&self, mut pairs: impl Iterator<Item = Pair<'a, Rule>>) -> Option<Rc<Exp>> {
+        let first = pairs.next()?;
+        match first.as_rule() {
+            Rule::arrow => Some(Rc::new(Exp::CALL {
+                operator: Rc::new(self.build_exp(inner1!(first))?),
+                operands: vec![temporary()],
+            })),
+            _ => self.build_sequence(std::iter::once(first).chain(pairs)),
+        }
+    }
+
+    fn build_bindings(&self, pair: Pair<Rule>) -> Option<Vec<(String, Rc<Exp>)>> {
+        let mut bindings = Vec::new();
+        for pair in pair.into_inner() {
             let mut pairs = pair.into_inner();
             let identifier = pairs.next()?.as_str().to_string();
             let expression = Rc::new(self.build_exp(pairs.next()?)?);
-            definitions.push((identifier, expression));
+            bindings.push((identifier, expression));
         }
-        let body = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
-        Some(Exp::LITERIAL(Rc::new(Datum::LAMBDA(Rc::new(Lambda {
-            ifvarlen,
-            parameters,
-            definitions,
-            body,
-        })))))
+        Some(bindings)
+    }
+
+    fn build_mv_bindings(&self, pair: Pair<Rule>) -> Option<Vec<(Formals, Rc<Exp>)>> {
+        let mut bindings = Vec::new();
+        for pair in pair.into_inner() {
+            let mut pairs = pair.into_inner();
+            let formals = self.build_formals(pairs.next()?)?;
+            let expression = Rc::new(self.build_exp(pairs.next()?)?);
+            bindings.push((formals, expression));
+        }
+        Some(bindings)
     }
 
     fn build_cond(&self, pair: Pair<Rule>) -> Option<Exp> {
@@ -160,92 +722,234 @@ impl PestParser {
         let mut alternative = None;
         let test = Some(Rc::new(self.build_exp(inner2!(pairs.next()?))?));
         let consequent = Some(Rc::new(self.build_exp(inner2!(pairs.next()?))?));
-        if let Some(pair) = pairs.next() {
-            alternative = Some(Rc::new(self.build_exp(inner2!(pair))?));
+        // `(if test consequent)` leaves the alternate empty
+        if let Some(exp) = pairs.next().and_then(|pair| pair.into_inner().next()) {
+            alternative = Some(Rc::new(self.build_exp(exp)?));
         }
-        return Some(Exp::COND {
+        Some(Exp::COND {
             test: test?,
             consequent: consequent?,
             alternative,
-        });
+        })
     }
 
     fn build_derived(&self, pair: Pair<Rule>) -> Option<Exp> {
         let pair = pair.into_inner().next()?;
         match pair.as_rule() {
-            Rule::no_else_cond => {
-                let mut current = Some(Exp::LITERIAL(Rc::new(Datum::PRIMITIVE(Rc::new(Primitive::NIL)))));
-                let mut conds = pair.into_inner().rev();
-                while let Some(pair) = conds.next() {
-                    let mut pairs = pair.into_inner();
-                    let test = Rc::new(self.build_exp(inner2!(pairs.next()?))?);
-                    let consequent = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
-                    current = Some(Exp::COND {
-                        test,
-                        consequent,
-                        alternative: Some(Rc::new(current?)),
-                    });
+            Rule::include => self.build_include_exp(pair),
+            Rule::cond_exp => {
+                let mut clauses: Vec<_> = pair.into_inner().collect();
+                let mut current = match clauses.last().map(|clause| clause.as_rule()) {
+                    Some(Rule::cond_else) => Some(self.build_sequence(clauses.pop()?.into_inner())?),
+                    _ => None,
+                };
+                for clause in clauses.into_iter().rev() {
+                    let mut pairs = clause.into_inner();
+                    let test = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
+                    let rest: Vec<_> = pairs.collect();
+                    current = Some(Rc::new(match rest.first().map(|pair| pair.as_rule()) {
+                        // ((lambda (t) (if t (receiver t) rest)) test)
+                        Some(Rule::arrow) => {
+                            let receiver = self.build_exp(inner1!(rest[0].clone()))?;
+                            let consequent = Exp::CALL {
+                                operator: Rc::new(receiver),
+                                operands: vec![temporary()],
+                            };
+                            let_temporary(test, if_exp(temporary(), Rc::new(consequent), current))
+                        }
+                        None => let_temporary(test, if_exp(temporary(), temporary(), current)),
+                        _ => if_exp(test, self.build_sequence(rest.into_iter())?, current),
+                    }));
                 }
-                current
+                Some(match current {
+                    Some(current) => Rc::try_unwrap(current).ok()?,
+                    None => unspecified(),
+                })
             }
-            Rule::else_cond => {
+            // ((lambda (t) (if (eqv? t 'datum) ... body rest)) key)
+            Rule::case_exp => {
                 let mut pairs = pair.into_inner();
-                let mut current = Some(self.build_exp(inner1!(pairs.next()?))?);
-                let mut conds = pairs.next()?.into_inner().rev();
-                while let Some(pair) = conds.next() {
-                    let mut pairs = pair.into_inner();
-                    let test = Rc::new(self.build_exp(inner2!(pairs.next()?))?);
-                    let consequent = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
-                    current = Some(Exp::COND {
-                        test,
-                        consequent,
-                        alternative: Some(Rc::new(current?)),
-                    });
+                let key = Rc::new(self.build_exp(pairs.next()?)?);
+                let mut clauses: Vec<_> = pairs.collect();
+                let mut current = match clauses.last().map(|clause| clause.as_rule()) {
+                    Some(Rule::case_else) => Some(self.build_case_body(clauses.pop()?.into_inner())?),
+                    _ => None,
+                };
+                for clause in clauses.into_iter().rev() {
+                    let mut pairs = clause.into_inner().peekable();
+                    let mut data = Vec::new();
+                    while let Some(pair) = pairs.next_if(|pair| pair.as_rule() == Rule::datum) {
+                        data.push(self.build_datum(pair)?);
+                    }
+                    let mut test = literal(Primitive::BOOLEAN(false));
+                    for datum in data.into_iter().rev() {
+                        let matches = call(" eqv?", vec![Exp::IDENTIFIER(TEMPORARY.to_string()), Exp::LITERIAL(Rc::new(datum))]);
+                        test = Rc::new(if_exp(Rc::new(matches), literal(Primitive::BOOLEAN(true)), Some(test)));
+                    }
+                    current = Some(Rc::new(if_exp(test, self.build_case_body(pairs)?, current)));
                 }
-                current
+                let body = match current {
+                    Some(current) => current,
+                    None => Rc::new(unspecified()),
+                };
+                Some(let_temporary(key, Rc::try_unwrap(body).ok()?))
             }
             Rule::and => {
-                let mut current = Some(Exp::LITERIAL(Rc::new(Datum::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true))))));
-                let mut pairs = pair.into_inner().rev();
-                while let Some(pair) = pairs.next() {
-                    let test = Rc::new(self.build_exp(inner2!(pair))?);
-                    current = Some(Exp::COND {
-                        test,
-                        consequent: Rc::new(current?),
-                        alternative: Some(Rc::new(Exp::LITERIAL(Rc::new(Datum::PRIMITIVE(Rc::new(Primitive::BOOLEAN(false))))))),
-                    });
+                let mut tests = Vec::new();
+                for pair in pair.into_inner() {
+                    tests.push(Rc::new(self.build_exp(inner1!(pair))?));
                 }
-                current
+                let mut current = tests.pop().unwrap_or_else(|| literal(Primitive::BOOLEAN(true)));
+                for test in tests.into_iter().rev() {
+                    current = Rc::new(if_exp(test, current, Some(literal(Primitive::BOOLEAN(false)))));
+                }
+                Rc::try_unwrap(current).ok()
             }
+            // ((lambda (t) (if t t rest)) test)
             Rule::or => {
-                let mut current = Some(Exp::LITERIAL(Rc::new(Datum::PRIMITIVE(Rc::new(Primitive::BOOLEAN(false))))));
-                let mut pairs = pair.into_inner().rev();
-                while let Some(pair) = pairs.next() {
-                    let test = Rc::new(self.build_exp(inner2!(pair))?);
-                    current = Some(Exp::COND {
-                        test,
-                        consequent: Rc::new(Exp::LITERIAL(Rc::new(Datum::PRIMITIVE(Rc::new(Primitive::BOOLEAN(true)))))),
-                        alternative: Some(Rc::new(current?)),
+                let mut tests = Vec::new();
+                for pair in pair.into_inner() {
+                    tests.push(Rc::new(self.build_exp(inner1!(pair))?));
+                }
+                let mut current = tests.pop().unwrap_or_else(|| literal(Primitive::BOOLEAN(false)));
+                for test in tests.into_iter().rev() {
+                    current = Rc::new(let_temporary(test, if_exp(temporary(), temporary(), Some(current))));
+                }
+                Rc::try_unwrap(current).ok()
+            }
+            Rule::when_exp => {
+                let mut pairs = pair.into_inner();
+                let test = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
+                Some(if_exp(test, self.build_sequence(pairs)?, None))
+            }
+            Rule::unless_exp => {
+                let mut pairs = pair.into_inner();
+                let test = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
+                let body = self.build_sequence(pairs)?;
+                Some(if_exp(test, Rc::new(unspecified()), Some(body)))
+            }
+            // (let loop ((var init) ...) (if test (begin result ...) (begin command ... (loop step ...))))
+            Rule::do_exp => {
+                let mut pairs = pair.into_inner().peekable();
+                let mut bindings = Vec::new();
+                let mut steps = Vec::new();
+                while let Some(spec) = pairs.next_if(|pair| pair.as_rule() == Rule::iteration_spec) {
+                    let mut spec = spec.into_inner();
+                    let identifier = spec.next()?.as_str().to_string();
+                    bindings.push((identifier.clone(), Rc::new(self.build_exp(spec.next()?)?)));
+                    steps.push(match spec.next() {
+                        Some(step) => Rc::new(self.build_exp(step)?),
+                        None => Rc::new(Exp::IDENTIFIER(identifier)),
                     });
                 }
-                current
+                let mut result = pairs.next()?.into_inner();
+                let test = Rc::new(self.build_exp(inner1!(result.next()?))?);
+                let result = match result.peek() {
+                    Some(_) => self.build_sequence(result)?,
+                    None => Rc::new(unspecified()),
+                };
+                let mut commands = Vec::new();
+                for pair in pairs {
+                    commands.push(Rc::new(self.build_exp(pair)?));
+                }
+                commands.push(Rc::new(Exp::CALL {
+                    operator: Rc::new(Exp::IDENTIFIER(DO_LOOP.to_string())),
+                    operands: steps,
+                }));
+                let body = if_exp(test, result, Some(sequence(commands)));
+                Some(named_let(DO_LOOP.to_string(), bindings, Vec::new(), vec![Rc::new(body)]))
+            }
+            // (delay-force (lambda () e)) with the internal delay-force
+            Rule::delay_force => Some(promise(" delay-force", self.build_exp(inner1!(pair))?)),
+            Rule::delay_exp => Some(promise(" delay", self.build_exp(inner1!(pair))?)),
+            // (delay (cons (delay a) (delay-force b)))
+            Rule::stream_cons => {
+                let mut pairs = pair.into_inner();
+                let first = promise(" delay", self.build_exp(pairs.next()?)?);
+                let rest = promise(" delay-force", self.build_exp(pairs.next()?)?);
+                Some(promise(" delay", call(" cons", vec![first, rest])))
             }
+            // (" parameterize" (lambda () body) param value ...)
+            Rule::parameterize => {
+                let mut pairs = pair.into_inner().peekable();
+                let mut bindings = Vec::new();
+                while let Some(binding) = pairs.next_if(|pair| pair.as_rule() == Rule::param_binding) {
+                    let mut binding = binding.into_inner();
+                    bindings.push(self.build_exp(binding.next()?)?);
+                    bindings.push(self.build_exp(binding.next()?)?);
+                }
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                let thunk = lambda(fixed(Vec::new()), definitions, body);
+                Some(call(" parameterize", std::iter::once(thunk).chain(bindings).collect()))
+            }
+            // ((lambda (x ...) body) e ...)
             Rule::let_exp => {
                 let mut pairs = pair.into_inner();
-                let mut definitions = Vec::new();
-                for pair in pairs.next()?.into_inner() {
-                    let mut pairs = pair.into_inner();
-                    let identifier = pairs.next()?.as_str().to_string();
-                    let expression = Rc::new(self.build_exp(pairs.next()?)?);
-                    definitions.push((identifier, expression));
-                }
-                let body = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
-                Some(Exp::LITERIAL(Rc::new(Datum::LAMBDA(Rc::new(Lambda {
-                    ifvarlen: false,
-                    parameters: Vec::new(),
-                    definitions,
-                    body,
-                })))))
+                let bindings = self.build_bindings(pairs.next()?)?;
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                let (parameters, operands) = bindings.into_iter().unzip();
+                Some(Exp::CALL {
+                    operator: Rc::new(lambda(fixed(parameters), definitions, body)),
+                    operands,
+                })
+            }
+            // (let (first) (let* (rest ...) body))
+            Rule::let_star => {
+                let mut pairs = pair.into_inner();
+                let mut bindings = self.build_bindings(pairs.next()?)?;
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                // the innermost let carries the body, `(let* () body)` is `(let () body)`
+                let (parameters, operands) = bindings.pop().into_iter().unzip();
+                let mut current = Exp::CALL {
+                    operator: Rc::new(lambda(fixed(parameters), definitions, body)),
+                    operands,
+                };
+                for (identifier, expression) in bindings.into_iter().rev() {
+                    current = Exp::CALL {
+                        operator: Rc::new(lambda(fixed(vec![identifier]), Vec::new(), vec![Rc::new(current)])),
+                        operands: vec![expression],
+                    };
+                }
+                Some(current)
+            }
+            Rule::let_values => {
+                let mut pairs = pair.into_inner();
+                let bindings = self.build_mv_bindings(pairs.next()?)?;
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                Some(let_values(bindings, definitions, body))
+            }
+            // (let-values (first) (let*-values (rest ...) body))
+            Rule::let_star_values => {
+                let mut pairs = pair.into_inner();
+                let mut bindings = self.build_mv_bindings(pairs.next()?)?;
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                let mut current = let_values(bindings.pop().into_iter().collect(), definitions, body);
+                for binding in bindings.into_iter().rev() {
+                    current = let_values(vec![binding], Vec::new(), vec![Rc::new(current)]);
+                }
+                Some(current)
+            }
+            // ((lambda () (define x e) ... body)), definitions are evaluated in order
+            Rule::letrec => {
+                let mut pairs = pair.into_inner();
+                let bindings = self.build_bindings(pairs.next()?)?;
+                let (inner, body) = self.build_body(pairs.next()?)?;
+                let mut definitions: Definitions = bindings
+                    .into_iter()
+                    .map(|(identifier, expression)| (Target::IDENTIFIER(identifier), expression))
+                    .collect();
+                definitions.extend(inner);
+                Some(Exp::CALL {
+                    operator: Rc::new(lambda(fixed(Vec::new()), definitions, body)),
+                    operands: Vec::new(),
+                })
+            }
+            Rule::named_let => {
+                let mut pairs = pair.into_inner();
+                let name = pairs.next()?.as_str().to_string();
+                let bindings = self.build_bindings(pairs.next()?)?;
+                let (definitions, body) = self.build_body(pairs.next()?)?;
+                Some(named_let(name, bindings, definitions, body))
             }
             _ => unreachable!(),
         }
@@ -255,6 +959,7 @@ impl PestParser {
     // Derived Form can be transformed to normal expression
     fn build_exp(&self, pair: Pair<Rule>) -> Option<Exp> {
         match pair.as_rule() {
+            Rule::exp => self.build_exp(inner1!(pair)),
             Rule::identifier => {
                 let identifier = pair.as_str().to_string();
                 Some(Exp::IDENTIFIER(identifier))
@@ -265,6 +970,8 @@ impl PestParser {
             }
             Rule::call => self.build_call(pair),
             Rule::lambda => self.build_lambda(pair),
+            Rule::case_lambda => self.build_case_lambda(pair),
+            Rule::quasiquotation => self.build_quasi(inner1!(pair), 1),
             Rule::cond => self.build_cond(pair),
             Rule::derived => self.build_derived(pair),
             _ => unreachable!(),
@@ -272,10 +979,129 @@ impl PestParser {
     }
 
     // Traverse the parse tree to build the AST
+    fn build_import_set(&self, pair: Pair<Rule>) -> Option<ImportSet> {
+        let pair = inner1!(pair);
+        let rule = pair.as_rule();
+        if rule == Rule::library_name {
+            return Some(ImportSet::LIBRARY(self.build_library_name(pair)));
+        }
+        let mut pairs = pair.into_inner();
+        let set = Box::new(self.build_import_set(pairs.next()?)?);
+        let identifiers = pairs.clone().map(|pair| pair.as_str().to_string());
+        Some(match rule {
+            Rule::only => ImportSet::ONLY(set, identifiers.collect()),
+            Rule::except => ImportSet::EXCEPT(set, identifiers.collect()),
+            Rule::prefix => ImportSet::PREFIX(set, pairs.next()?.as_str().to_string()),
+            Rule::rename => ImportSet::RENAME(set, self.build_renames(pairs)?),
+            _ => unreachable!(),
+        })
+    }
+
+    fn build_renames(&self, pairs: Pairs<Rule>) -> Option<Vec<(String, String)>> {
+        pairs
+            .map(|pair| {
+                let mut pairs = pair.into_inner();
+                let from = pairs.next()?.as_str().to_string();
+                let to = pairs.next()?.as_str().to_string();
+                Some((from, to))
+            })
+            .collect()
+    }
+
+    fn build_library_name(&self, pair: Pair<Rule>) -> LibraryName {
+        pair.into_inner().map(|part| part.as_str().to_string()).collect()
+    }
+
+    fn build_import(&self, pair: Pair<Rule>) -> Option<Top> {
+        let sets = pair
+            .into_inner()
+            .map(|pair| self.build_import_set(pair))
+            .collect::<Option<_>>()?;
+        Some(Top::IMPORT(sets))
+    }
+
+    fn build_library(&self, pair: Pair<Rule>) -> Option<Library> {
+        let mut pairs = pair.into_inner();
+        let name = self.build_library_name(pairs.next()?);
+        let mut exports = Vec::new();
+        let mut body = Vec::new();
+        for declaration in pairs {
+            let declaration = inner1!(declaration);
+            match declaration.as_rule() {
+                Rule::export => {
+                    for spec in declaration.into_inner() {
+                        let spec = inner1!(spec);
+                        if spec.as_rule() == Rule::export_rename {
+                            let mut names = spec.into_inner();
+                            let internal = names.next()?.as_str().to_string();
+                            let external = names.next()?.as_str().to_string();
+                            exports.push((internal, external));
+                        } else {
+                            let identifier = spec.as_str().to_string();
+                            exports.push((identifier.clone(), identifier));
+                        }
+                    }
+                }
+                Rule::import => body.push(self.build_import(declaration)?),
+                Rule::include => body.extend(self.build_include(declaration)?),
+                Rule::library_begin => body.extend(self.build_ast(declaration.into_inner())?),
+                _ => unreachable!(),
+            }
+        }
+        Some(Library {
+            name,
+            exports,
+            body,
+        })
+    }
+
+    // The tops of the included files in order
+    fn build_include(&self, pair: Pair<Rule>) -> Option<Vec<Top>> {
+        let mut pairs = pair.into_inner();
+        let fold = pairs.next()?.as_str() == "include-ci";
+        let name = if fold { "include-ci" } else { "include" };
+        let mut tops = Vec::new();
+        for file in pairs {
+            let text = file.as_str();
+            let path = build_string(&text[1..text.len() - 1])?;
+            let included = read_source(&path, name, |program| {
+                let program = if fold { fold_case(&program) } else { program };
+                let mut pairs = PestDriver::parse(Rule::prog, &program)
+                    .unwrap_or_else(|e| panic!("{}: {}: {}", name, path, e));
+                self.build_ast(pairs.next()?.into_inner())
+            })?;
+            tops.extend(included);
+        }
+        Some(tops)
+    }
+
+    // An include in an expression is a body of its own
+    fn build_include_exp(&self, pair: Pair<Rule>) -> Option<Exp> {
+        let mut definitions = Vec::new();
+        let mut body = Vec::new();
+        for top in self.build_include(pair)? {
+            match top {
+                Top::DEC { target, expression } => definitions.push((target, expression)),
+                Top::EXP { expression } => body.push(expression),
+                _ => panic!("include: imports and libraries are only allowed at top level"),
+            }
+        }
+        if body.is_empty() {
+            body.push(Rc::new(unspecified()));
+        }
+        Some(Exp::CALL {
+            operator: Rc::new(lambda(fixed(Vec::new()), definitions, body)),
+            operands: Vec::new(),
+        })
+    }
+
     fn build_ast(&self, pairs: Pairs<Rule>) -> Option<Vec<Top>> {
         let mut tops = Vec::new();
         for pair in pairs {
             match pair.as_rule() {
+                Rule::include => tops.extend(self.build_include(pair)?),
+                Rule::import => tops.push(self.build_import(pair)?),
+                Rule::define_library => tops.push(Top::LIBRARY(self.build_library(pair)?)),
                 Rule::exp => {
                     let pair = inner1!(pair);
                     tops.push(Top::EXP {
@@ -283,13 +1109,8 @@ impl PestParser {
                     });
                 }
                 Rule::def => {
-                    let mut pairs = pair.into_inner();
-                    let identifier = pairs.next()?.as_str().to_string();
-                    let expression = Rc::new(self.build_exp(inner1!(pairs.next()?))?);
-                    tops.push(Top::DEC {
-                        identifier,
-                        expression,
-                    });
+                    let (target, expression) = self.build_definition(pair)?;
+                    tops.push(Top::DEC { target, expression });
                 }
                 Rule::EOI => {
                     return Some(tops);
@@ -301,6 +1122,17 @@ impl PestParser {
     }
 }
 
+impl PestParser {
+    // The datum written as the whole of the text
+    pub fn parse_datum(&self, text: &str) -> Option<Datum> {
+        let pair = PestDriver::parse(Rule::datum, text).ok()?.next()?;
+        if pair.as_span().end() != text.len() {
+            return None;
+        }
+        self.build_datum(pair)
+    }
+}
+
 impl SchemeParser for PestParser {
     fn parse(&self, program: &str) -> Ast {
         let mut pairs = PestDriver::parse(Rule::prog, program);
@@ -0,0 +1,132 @@
+use crate::builtin::is_internal;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+// Groups of builtins reaching outside the interpreter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    // file ports, `load`, and reading the files of `include` and libraries
+    FILES,
+    // evaluating code built at run time and getting hold of environments
+    EVAL,
+}
+
+impl Capability {
+    pub fn builtins(&self) -> &'static [&'static str] {
+        match self {
+            Capability::FILES => &[
+                "open-input-file",
+                "open-binary-input-file",
+                "open-output-file",
+                "open-binary-output-file",
+                "call-with-input-file",
+                "call-with-output-file",
+                "with-input-from-file",
+                "with-output-to-file",
+                "file-exists?",
+                "delete-file",
+                "load",
+            ],
+            Capability::EVAL => &[
+                "eval",
+                "environment",
+                "scheme-report-environment",
+                "null-environment",
+                "interaction-environment",
+                "load",
+            ],
+        }
+    }
+}
+
+// What the scripts of an interpreter are permitted, applied to every
+// environment created under it, those of libraries and `environment` included.
+// The internal builtins support derived forms and are always bound, source
+// can't spell their names.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    // None allows every builtin not denied
+    allowed: Option<HashSet<String>>,
+    denied: HashSet<String>,
+    files: bool,
+    sealed: bool,
+}
+
+impl Policy {
+    // Everything permitted, nothing sealed
+    pub fn permissive() -> Self {
+        Self::default()
+    }
+    // For untrusted code: no files, no eval, the initial bindings sealed
+    pub fn sandbox() -> Self {
+        Self::permissive()
+            .deny_capability(Capability::FILES)
+            .deny_capability(Capability::EVAL)
+            .sealed(true)
+    }
+    // Only these builtins are bound, along with those allowed before
+    pub fn allow<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.allowed
+            .get_or_insert_with(HashSet::new)
+            .extend(names.into_iter().map(String::from));
+        self
+    }
+    pub fn deny<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.denied.extend(names.into_iter().map(String::from));
+        self
+    }
+    pub fn deny_capability(mut self, capability: Capability) -> Self {
+        if capability == Capability::FILES {
+            self.files = true;
+        }
+        self.deny(capability.builtins().iter().copied())
+    }
+    // A sealed environment keeps the bindings it was created with, scripts
+    // may only define new names
+    pub fn sealed(mut self, sealed: bool) -> Self {
+        self.sealed = sealed;
+        self
+    }
+    pub fn is_sealed(&self) -> bool {
+        self.sealed
+    }
+    pub fn permits(&self, name: &str) -> bool {
+        is_internal(name)
+            || (!self.denied.contains(name)
+                && self.allowed.as_ref().is_none_or(|allowed| allowed.contains(name)))
+    }
+    pub fn permits_files(&self) -> bool {
+        !self.files
+    }
+}
+
+thread_local! {
+    static POLICY: RefCell<Rc<Policy>> = RefCell::new(Rc::new(Policy::default()));
+}
+
+// The policy in force while the current interpreter evaluates
+pub fn current() -> Rc<Policy> {
+    POLICY.with(|policy| policy.borrow().clone())
+}
+
+// Run `f` under the policy
+pub fn install<T>(policy: &Rc<Policy>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Rc<Policy>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(saved) = self.0.take() {
+                POLICY.with(|policy| policy.replace(saved));
+            }
+        }
+    }
+    let _restore = Restore(Some(POLICY.with(|current| current.replace(policy.clone()))));
+    f()
+}
+
+// Reading source files is refused, with the name of what tried
+pub fn check_files(name: &str) {
+    if !current().permits_files() {
+        panic!("{}: reading files is not permitted", name);
+    }
+}
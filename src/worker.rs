@@ -0,0 +1,116 @@
+use crate::limits::{Interrupt, LimitExceeded};
+use crate::{Katsu, KatsuBuilder};
+use std::sync::mpsc::{channel, Sender};
+use std::thread::JoinHandle;
+
+// Worker
+//
+// An interpreter running on a thread of its own. `Katsu` itself is not `Send`,
+// its values are reference counted without atomics, but the handle to a
+// worker is, so it can be kept by any thread. Values can't leave the interpreter's
+// thread, results come back printed as `Katsu::eval_to_str` prints them, and
+// scripts failing panic in the caller as they would with `Katsu::eval`.
+//
+// A worker runs the jobs sent to it one at a time, isolated ones included;
+// for evaluations to run in parallel, spawn a worker for each.
+
+type Reply = std::thread::Result<Result<Vec<String>, LimitExceeded>>;
+
+struct Job {
+    program: String,
+    // in a fresh instance from the snapshot instead of the lasting one
+    isolated: bool,
+    reply: Sender<Reply>,
+}
+
+pub struct Worker {
+    jobs: Option<Sender<Job>>,
+    interrupt: Interrupt,
+    thread: Option<JoinHandle<()>>,
+}
+
+fn print(katsu: &Katsu, program: &str) -> Result<Vec<String>, LimitExceeded> {
+    Ok(katsu
+        .try_eval(program)?
+        .into_iter()
+        .map(|v| match v {
+            Some(v) => format!("{}", v),
+            None => String::from("None"),
+        })
+        .collect())
+}
+
+impl Worker {
+    // The interpreter is built on the worker's thread and runs `setup`, then is
+    // snapshotted for the isolated evaluations, each instantiated anew
+    pub fn spawn(builder: KatsuBuilder, setup: &str) -> Self {
+        let (jobs, received) = channel::<Job>();
+        let interrupt = Interrupt::default();
+        let shared = interrupt.clone();
+        let setup = setup.to_string();
+        let thread = std::thread::spawn(move || {
+            let mut katsu = builder.build();
+            katsu.eval(&setup);
+            let snapshot = katsu.snapshot();
+            katsu.interrupt = shared.clone();
+            for job in received {
+                let run = std::panic::AssertUnwindSafe(|| {
+                    if job.isolated {
+                        let mut instance = snapshot.instantiate();
+                        instance.interrupt = shared.clone();
+                        print(&instance, &job.program)
+                    } else {
+                        print(&katsu, &job.program)
+                    }
+                });
+                // the caller may have gone, nobody is left to tell
+                let _ = job.reply.send(std::panic::catch_unwind(run));
+            }
+        });
+        Self {
+            jobs: Some(jobs),
+            interrupt,
+            thread: Some(thread),
+        }
+    }
+    fn submit(&self, program: &str, isolated: bool) -> Result<Vec<String>, LimitExceeded> {
+        let (reply, received) = channel();
+        let job = Job {
+            program: program.to_string(),
+            isolated,
+            reply,
+        };
+        self.jobs
+            .as_ref()
+            .expect("the worker is running")
+            .send(job)
+            .expect("the worker is running");
+        received
+            .recv()
+            .expect("the worker replies")
+            .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+    }
+    // In the interpreter kept by the worker, definitions stay for later calls
+    pub fn try_eval(&self, program: &str) -> Result<Vec<String>, LimitExceeded> {
+        self.submit(program, false)
+    }
+    // In a fresh instance from the interpreter as it was set up
+    pub fn try_eval_isolated(&self, program: &str) -> Result<Vec<String>, LimitExceeded> {
+        self.submit(program, true)
+    }
+    // Stops the evaluation the worker is running, of either kind, or the next
+    // one when none is
+    pub fn interrupt_handle(&self) -> Interrupt {
+        self.interrupt.clone()
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // the thread finishes once no more jobs can come
+        self.jobs.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
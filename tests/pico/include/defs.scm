@@ -0,0 +1,4 @@
+; definitions spliced into the including program
+(define included-value 42)
+(define (included-double x) (* 2 x))
+(include "sub/nested.scm")
@@ -0,0 +1,2 @@
+(define local 1)
+(+ local 10)
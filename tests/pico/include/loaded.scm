@@ -0,0 +1,2 @@
+(define loaded-value (+ 1 2))
+(load "sub/loaded-nested.scm")
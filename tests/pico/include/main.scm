@@ -0,0 +1,4 @@
+; paths are relative to this file
+(include "defs.scm")
+(include-ci "upper.scm")
+(define expression-value (include "exprs.scm" "exprs.scm"))
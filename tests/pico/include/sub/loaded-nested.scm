@@ -0,0 +1 @@
+(define loaded-nested-value 'deep)
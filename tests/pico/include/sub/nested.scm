@@ -0,0 +1,2 @@
+; included relative to defs.scm
+(define nested-value "nested")
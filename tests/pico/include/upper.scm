@@ -0,0 +1,2 @@
+(DEFINE Shouted-Value "Keeps Case")
+(Define (SHOUT) #\A)
@@ -1,4 +1,7 @@
 // reference to src/interpreter.rs
+use katsu::limits::{Limit, Limits};
+use katsu::policy::{Capability, Policy};
+use katsu::worker::Worker;
 use katsu::Katsu;
 use regex::Regex;
 
@@ -8,32 +11,426 @@ macro_rules! cap_to_string {
     };
 }
 
-#[test]
-fn run_all_test() {
+// the value after ; in each line is the expected result, optionally followed by
+// `; out>` and what the line prints, with `\n` for a newline
+fn run_tests(tests: &str) {
     std::env::set_var("PARSER", "pest");
-    let katsu = Katsu::new();
-    let tests = include_str!("test.ss");
-    // the value after ; in each line is the expected result
+    run_tests_in(Katsu::new(), tests);
+}
+
+fn run_tests_in(mut katsu: Katsu, tests: &str) {
+    let output = katsu.capture_output();
     let res = katsu.eval_all(tests);
-    let re = Regex::new(r"(.*);.*>(.*)\n").unwrap();
-    for (i, ((expr, expected), actual)) in 
+    let re = Regex::new(r"(.*);\s*>(.*?)(?:\s*;\s*out>(.*))?\n").unwrap();
+    for (i, ((expr, expected, printed), actual)) in 
     re.captures_iter(tests)
-    .map(|c| (cap_to_string!(c, 1), cap_to_string!(c, 2)))
+    .map(|c| (cap_to_string!(c, 1), cap_to_string!(c, 2), c.get(3).map(|m| m.as_str().replace("\\n", "\n"))))
     .zip(res)
     .enumerate() {
         print!("epxr{}:{} => expected: {}, actual: {}", i, expr, expected, actual);
         assert_eq!(expected, actual);
+        let actual_printed = output.take();
+        if let Some(printed) = printed {
+            assert_eq!(printed, actual_printed);
+        }
         println!("    \x1b[32mtest {} pass!\x1b[0m", i);
     }
 }
 
+#[test]
+fn run_all_test() {
+    run_tests(include_str!("test.ss"));
+}
+
+#[test]
+fn char_test() {
+    run_tests(include_str!("char.ss"));
+}
+
+#[test]
+fn string_test() {
+    run_tests(include_str!("string.ss"));
+}
+
+#[test]
+fn vector_test() {
+    run_tests(include_str!("vector.ss"));
+}
+
+#[test]
+fn list_test() {
+    run_tests(include_str!("list.ss"));
+}
+
+#[test]
+fn equivalence_test() {
+    run_tests(include_str!("equivalence.ss"));
+}
+
+#[test]
+fn let_test() {
+    run_tests(include_str!("let.ss"));
+}
+
+#[test]
+fn define_test() {
+    run_tests(include_str!("define.ss"));
+}
+
+#[test]
+fn lambda_test() {
+    run_tests(include_str!("lambda.ss"));
+}
+
+#[test]
+fn quasiquote_test() {
+    run_tests(include_str!("quasiquote.ss"));
+}
+
+#[test]
+fn control_test() {
+    run_tests(include_str!("control.ss"));
+}
+
+#[test]
+fn truthiness_test() {
+    run_tests(include_str!("truthiness.ss"));
+}
+
+#[test]
+fn record_test() {
+    run_tests(include_str!("record.ss"));
+}
+
+#[test]
+fn promise_test() {
+    run_tests(include_str!("promise.ss"));
+}
+
+#[test]
+fn hashtable_test() {
+    run_tests(include_str!("hashtable.ss"));
+}
+
+#[test]
+fn port_test() {
+    run_tests(include_str!("port.ss"));
+}
+
+#[test]
+fn eval_test() {
+    run_tests(include_str!("eval.ss"));
+}
+
+#[test]
+fn library_test() {
+    std::env::set_var("PARSER", "pest");
+    run_tests_in(Katsu::builder().library_path("tests/pico/lib").build(), include_str!("library.ss"));
+}
+
+#[test]
+fn library_instance_test() {
+    std::env::set_var("PARSER", "pest");
+    let first = Katsu::new();
+    first.add_library_path("tests/pico/lib");
+    first.eval("(define-library (mine) (export x) (begin (define x 1)))");
+    first.eval("(import (mine) (greet hello))");
+    assert_eq!(first.eval_to_str("x"), "1");
+    // neither the libraries nor the search path reach another interpreter
+    let second = Katsu::new();
+    assert!(eval_error_in(&second, "(import (mine))").contains("error"));
+    assert!(eval_error_in(&second, "(import (greet hello))").contains("error"));
+}
+
+#[test]
+fn include_test() {
+    run_tests(include_str!("include.ss"));
+}
+
+#[test]
+fn prelude_test() {
+    run_tests(include_str!("prelude.ss"));
+}
+
+#[test]
+fn no_prelude_test() {
+    std::env::set_var("PARSER", "pest");
+    let katsu = Katsu::builder().prelude(false).build();
+    assert_eq!(katsu.eval_to_str("(zero? 0)"), "None");
+    assert_eq!(katsu.eval_to_str("(car '(1))"), "1");
+}
+
+fn limited(limits: Limits) -> Katsu {
+    std::env::set_var("PARSER", "pest");
+    Katsu::builder().limits(limits).build()
+}
+
+#[test]
+fn fuel_limit_test() {
+    let katsu = limited(Limits { fuel: Some(10_000), ..Limits::default() });
+    katsu.eval("(define (spin) (spin))");
+    let exceeded = katsu.try_eval("(spin)").unwrap_err();
+    assert_eq!(exceeded.limit, Limit::FUEL);
+    assert_eq!(exceeded.usage.steps, 10_001);
+    // the environment is kept, evaluation goes on once refueled
+    katsu.reset_usage();
+    assert_eq!(katsu.eval_to_str("(procedure? spin)"), "true");
+    assert_eq!(katsu.usage().steps, 3);
+}
+
+#[test]
+fn depth_limit_test() {
+    let katsu = limited(Limits { depth: Some(100), ..Limits::default() });
+    katsu.eval("(define (deep n) (if (= n 0) 0 (+ 1 (deep (- n 1)))))");
+    assert_eq!(katsu.try_eval("(deep 1000)").unwrap_err().limit, Limit::DEPTH);
+    assert_eq!(katsu.usage().depth, 0);
+    assert_eq!(katsu.eval_to_str("(deep 10)"), "10");
+    katsu.set_limits(Limits::default());
+    assert_eq!(katsu.eval_to_str("(deep 150)"), "150");
+}
+
+#[test]
+fn heap_limit_test() {
+    let katsu = limited(Limits { heap: Some(1 << 20), ..Limits::default() });
+    katsu.eval("(define (grow l) (grow (cons 1 l)))");
+    assert_eq!(katsu.try_eval("(grow '())").unwrap_err().limit, Limit::HEAP);
+    katsu.reset_usage();
+    // refused before anything is allocated
+    let exceeded = katsu.try_eval("(make-vector 1000000000000)").unwrap_err();
+    assert_eq!(exceeded.limit, Limit::HEAP);
+    assert!(katsu.usage().heap < 1024);
+    assert_eq!(katsu.eval_to_str("(vector-length (make-vector 10))"), "10");
+    // the pairs append and list-copy make count as well
+    katsu.reset_usage();
+    katsu.eval("(define l (make-list 500 0)) (define m (append l l l l l l l l l l))");
+    katsu.reset_usage();
+    assert_eq!(katsu.try_eval("(append m m m m)").unwrap_err().limit, Limit::HEAP);
+    katsu.reset_usage();
+    assert_eq!(katsu.try_eval("(list (list-copy m) (list-copy m) (list-copy m) (list-copy m))").unwrap_err().limit, Limit::HEAP);
+    katsu.set_limits(Limits::default());
+    katsu.eval("(define l 0) (define m 0)");
+}
+
+#[test]
+fn long_list_test() {
+    std::env::set_var("PARSER", "pest");
+    let katsu = Katsu::new();
+    // dropping it doesn't recurse along the list
+    katsu.eval("(define l (make-list 1000000 0)) (define l 0)");
+    assert_eq!(katsu.eval_to_str("l"), "0");
+}
+
+#[test]
+fn timeout_test() {
+    let katsu = limited(Limits { time: Some(std::time::Duration::from_millis(50)), ..Limits::default() });
+    katsu.eval("(define (spin) (spin))");
+    assert_eq!(katsu.try_eval("(spin)").unwrap_err().limit, Limit::TIME);
+    // each evaluation gets the time anew
+    assert_eq!(katsu.eval_to_str("(+ 1 2)"), "3");
+}
+
+#[test]
+fn interrupt_test() {
+    let katsu = limited(Limits::default());
+    katsu.eval("(define (spin) (spin))");
+    let interrupt = katsu.interrupt_handle();
+    // a trigger from before the evaluation still stops it, and is gone after
+    interrupt.trigger();
+    assert_eq!(katsu.try_eval("(+ 1 2)").unwrap_err().limit, Limit::INTERRUPT);
+    assert_eq!(katsu.eval_to_str("(+ 1 2)"), "3");
+    let trigger = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        interrupt.trigger();
+    });
+    let exceeded = katsu.try_eval("(spin)").unwrap_err();
+    trigger.join().unwrap();
+    assert_eq!(exceeded.limit, Limit::INTERRUPT);
+    assert!(exceeded.to_string().starts_with("interrupted at step "));
+    assert_eq!(katsu.eval_to_str("(+ 1 2)"), "3");
+}
+
+#[test]
+fn snapshot_test() {
+    std::env::set_var("PARSER", "pest");
+    let katsu = Katsu::new();
+    katsu.eval("(define (double x) (* 2 x)) (define v (vector 1))");
+    katsu.eval("(define (get) v) (define t (make-hash-table eq?)) (hash-table-set! t v 'v)");
+    let snapshot = katsu.snapshot();
+    katsu.eval("(define later 1)");
+    let first = snapshot.instantiate();
+    let second = snapshot.instantiate();
+    first.eval("(define x 1) (define double list)");
+    assert_eq!(first.eval_to_str("(double 2)"), "(2)");
+    assert_eq!(second.eval_to_str("(double 2) x later"), "4NoneNone");
+    assert_eq!(katsu.eval_to_str("(double 2) x later"), "4None1");
+    // eval in the interaction environment defines in the instance
+    second.eval("(eval '(define y 2) (interaction-environment))");
+    assert_eq!(second.eval_to_str("y"), "2");
+    assert_eq!(first.eval_to_str("y"), "None");
+    // mutable objects are copied for each instance
+    first.eval("(vector-set! v 0 99)");
+    katsu.eval("(vector-set! v 0 2)");
+    assert_eq!(first.eval_to_str("(get)"), "#(99)");
+    assert_eq!(second.eval_to_str("(get) (hash-table-ref/default t v #f)"), "#(1)v");
+    assert_eq!(snapshot.instantiate().eval_to_str("v"), "#(1)");
+    // the current port parameters stay those the instance installs its ports in
+    katsu.eval("(define out current-output-port) (define (say x) (display x (out)))");
+    let mut instance = katsu.snapshot().instantiate();
+    let output = instance.capture_output();
+    instance.eval("(say 1) (display 2 (out))");
+    assert_eq!(output.contents(), "12");
+}
+
+#[test]
+fn sealed_snapshot_test() {
+    let katsu = restricted(Policy::permissive().sealed(true));
+    let instance = katsu.snapshot().instantiate();
+    assert!(eval_error_in(&instance, "(define car cdr)").contains("error"));
+    assert!(eval_error_in(&katsu, "(define car cdr)").contains("error"));
+}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn worker_test() {
+    assert_send::<Worker>();
+    assert_send::<katsu::KatsuBuilder>();
+    std::env::set_var("PARSER", "pest");
+    let worker = Worker::spawn(Katsu::builder(), "(define (spin) (spin)) (define n 1) (define v (vector 1))");
+    let worker = std::thread::spawn(move || worker).join().unwrap();
+    worker.try_eval("(define n 2)").unwrap();
+    assert_eq!(worker.try_eval("n").unwrap(), vec!["2"]);
+    assert_eq!(worker.try_eval_isolated("(define m 3) (+ n m)").unwrap(), vec!["None", "4"]);
+    assert_eq!(worker.try_eval_isolated("m").unwrap(), vec!["None"]);
+    worker.try_eval_isolated("(vector-set! v 0 99)").unwrap();
+    assert_eq!(worker.try_eval_isolated("v").unwrap(), vec!["#(1)"]);
+    let interrupt = worker.interrupt_handle();
+    let trigger = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        interrupt.trigger();
+    });
+    assert_eq!(worker.try_eval_isolated("(spin)").unwrap_err().limit, Limit::INTERRUPT);
+    trigger.join().unwrap();
+    let failed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| worker.try_eval("(define x (car 1))")));
+    assert!(failed.is_err());
+    assert_eq!(worker.try_eval("n").unwrap(), vec!["2"]);
+}
+
+// the message of the panic evaluating the program
+fn eval_error(program: &str) -> String {
+    std::env::set_var("PARSER", "pest");
+    eval_error_in(&Katsu::new(), program)
+}
+
+fn eval_error_in(katsu: &Katsu, program: &str) -> String {
+    let error = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| katsu.eval(program)))
+        .err()
+        .unwrap();
+    error
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| error.downcast_ref::<&str>().map(|message| message.to_string()))
+        .unwrap()
+}
+
+#[test]
+fn include_error_test() {
+    assert!(eval_error(r#"(load "tests/pico/include/missing.scm")"#).starts_with("load: tests/pico/include/missing.scm: "));
+    assert!(eval_error(r#"(include "tests/pico/include/missing.scm")"#).starts_with("include: tests/pico/include/missing.scm: "));
+    assert!(eval_error(r#"(load "tests/pico/include/self.scm")"#).ends_with("self.scm includes itself"));
+}
+
+#[test]
+fn eval_error_test() {
+    assert_eq!(eval_error("(eval (list + 1 2) (interaction-environment))"), "eval: not a datum");
+    assert_eq!(eval_error("(scheme-report-environment 6)"), "scheme-report-environment: unsupported version");
+}
+
+#[test]
+fn quasiquote_error_test() {
+    assert_eq!(eval_error("`,@,x"), "quasiquote: unquote outside of a quasiquote");
+}
+
+#[test]
+fn host_ports_test() {
+    std::env::set_var("PARSER", "pest");
+    let mut katsu = Katsu::new();
+    katsu.set_input("first\nsecond".as_bytes());
+    let errors = katsu.capture_error();
+    let lines = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let sink = lines.clone();
+    katsu.set_output_callback(move |text| sink.borrow_mut().push(text.to_string()));
+    assert_eq!(katsu.eval_to_str("(read-line)"), "first");
+    katsu.eval("(display (read-line)) (display 1 (current-error-port))");
+    assert_eq!(*lines.borrow(), vec!["second"]);
+    assert_eq!(errors.take(), "1");
+}
+
+fn restricted(policy: Policy) -> Katsu {
+    std::env::set_var("PARSER", "pest");
+    Katsu::builder().policy(policy).build()
+}
+
+#[test]
+fn sandbox_test() {
+    let katsu = restricted(Policy::sandbox());
+    for name in ["open-input-file", "delete-file", "load", "eval", "environment"] {
+        assert_eq!(katsu.eval_to_str(name), "None", "{} is bound", name);
+    }
+    assert_eq!(katsu.eval_to_str("(max 1 (car '(2)))"), "2");
+    // libraries are built under the same policy
+    katsu.eval(r#"(define-library (peek) (export f) (begin (define (f) (open-input-file "x"))))"#);
+    katsu.eval("(import (peek) (scheme file))");
+    assert_eq!(katsu.eval_to_str("(f) open-output-file"), "NoneNone");
+    assert!(eval_error_in(&katsu, r#"(include "tests/pico/include/defs.scm")"#)
+        .starts_with("include: reading files is not permitted"));
+}
+
+#[test]
+fn sandbox_library_test() {
+    std::env::set_var("PARSER", "pest");
+    // a library of a permissive interpreter on the same thread isn't visible
+    let permissive = Katsu::new();
+    permissive.eval("(define-library (evil) (export open-output-file) (begin))");
+    let sandbox = restricted(Policy::sandbox());
+    assert!(eval_error_in(&sandbox, "(import (evil))").contains("error"));
+    // nor can a library of the sandbox export a denied name
+    sandbox.eval("(define-library (evil) (export eval) (begin (define (eval x) x)))");
+    sandbox.eval("(import (evil))");
+    assert_eq!(sandbox.eval_to_str("eval open-output-file"), "NoneNone");
+}
+
+#[test]
+fn sealed_test() {
+    let katsu = restricted(Policy::permissive().sealed(true));
+    katsu.eval("(define x 1) (define x 2) (define (f) (let ((car cdr)) (car '(1 2))))");
+    assert_eq!(katsu.eval_to_str("x (f)"), "2(2)");
+    assert!(eval_error_in(&katsu, "(define car cdr)").contains("error"));
+    assert!(eval_error_in(&katsu, "(define-values (abs y) (values 1 2))").contains("error"));
+    katsu.eval("(import (scheme base))");
+    assert_eq!(katsu.eval_to_str("(car '(1 2)) (abs (- 0 3))"), "13");
+}
+
+#[test]
+fn allowlist_test() {
+    let policy = Policy::permissive()
+        .allow(["car", "cdr", "+"])
+        .deny_capability(Capability::EVAL);
+    let katsu = restricted(policy);
+    assert_eq!(katsu.eval_to_str("(+ (car '(1)) 2)"), "3");
+    assert_eq!(katsu.eval_to_str("(cons 1 2)"), "None");
+    assert_eq!(katsu.eval_to_str("display"), "None");
+    assert_eq!(katsu.eval_to_str("(%cons 1 2)"), "None");
+    assert!(!katsu.policy().permits("%cons"));
+    assert!(katsu.policy().permits(" parameterize"));
+    assert!(!katsu.policy().permits("eval"));
+}
+
 #[test]
 fn test() {
     std::env::set_var("PARSER", "pest");
     let katsu = Katsu::new();
-    let prelude = "(define list (lambda l l))";
     let test = "(list 1 2 3)";
-    katsu.eval(prelude);
     let res = katsu.eval_to_str(test);
     println!("{}", res);
 }